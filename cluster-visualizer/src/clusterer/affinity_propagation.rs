@@ -3,6 +3,7 @@ use super::Clusterer;
 use super::pick_color;
 
 use cluster::misc::affinity_propagation::AffinityPropagation;
+use cluster::misc::affinity_propagation::median_preference;
 
 use math::prelude::*;
 
@@ -17,11 +18,13 @@ pub struct AffinityPropagationClusterer {
 }
 
 impl AffinityPropagationClusterer {
-    pub fn new(samples : Vec<Vector<2>>, preference : f64, damping : f64) -> Box<Self> {
+    pub fn new(samples : Vec<Vector<2>>, damping : f64) -> Box<Self> {
         let sample_values = samples;
         let sample_labels = vec![0; sample_values.len()];
 
-        let affinity_propagation = AffinityPropagation::new(&sample_values, |&sample1, &sample2| -(sample1 - sample2).squared_length(), preference);
+        let similarity = |&sample1 : &Vector<2>, &sample2 : &Vector<2>| -(sample1 - sample2).squared_length();
+        let preference = median_preference(&sample_values, similarity).unwrap_or(0.0);
+        let affinity_propagation = AffinityPropagation::new(&sample_values, similarity, preference);
         let exemplers = affinity_propagation.exemplers();
 
         Box::new(Self {
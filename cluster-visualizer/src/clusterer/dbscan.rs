@@ -3,19 +3,20 @@ use super::Clusterer;
 use super::pick_color;
 
 use cluster::misc::dbscan::dbscan;
+use cluster::misc::dbscan::DbscanResult;
+use cluster::misc::dbscan::SampleLabel;
 
 use math::prelude::*;
 
 pub struct DbscanClusterer {
     samples : Vec<Vector<2>>,
-    count : usize,
-    labels : Vec<usize>,
+    result : DbscanResult,
 }
 
 impl DbscanClusterer {
     pub fn new(samples : Vec<Vector<2>>, epsilon : f64, min_pts : usize) -> Box<Self> {
-        let (count, labels) = dbscan(&samples, epsilon, min_pts);
-        Box::new(Self { samples, count, labels  })
+        let result = dbscan(&samples, epsilon, min_pts);
+        Box::new(Self { samples, result })
     }
 }
 
@@ -27,15 +28,21 @@ impl Clusterer for DbscanClusterer {
     fn update(&mut self) {}
 
     fn render(&self, mut render : Render<'_>) {
-        for (&sample, &label) in std::iter::zip(&self.samples, &self.labels) {
-            if label != self.samples.len() {
-                let ratio = label as f64 / self.count as f64;
-                let (r, g, b) = pick_color(ratio);
-                render.draw_point(r, g, b, sample[0], sample[1], 5.0);
-            } else {
-                render.draw_point(255, 0, 0, sample[0], sample[1], 10.0);
+        for (&sample, &label) in std::iter::zip(&self.samples, &self.result.sample_labels) {
+            match label {
+                SampleLabel::Core(cluster_index) => {
+                    let ratio = cluster_index as f64 / self.result.cluster_count() as f64;
+                    let (r, g, b) = pick_color(ratio);
+                    render.draw_point(r, g, b, sample[0], sample[1], 8.0);
+                }
+                SampleLabel::Border(cluster_index) => {
+                    // Smaller and dimmer than core points of the same cluster.
+                    let ratio = cluster_index as f64 / self.result.cluster_count() as f64;
+                    let (r, g, b) = pick_color(ratio);
+                    render.draw_point(r / 2, g / 2, b / 2, sample[0], sample[1], 4.0);
+                }
+                SampleLabel::Noise => render.draw_point(128, 128, 128, sample[0], sample[1], 3.0),
             }
         }
     }
 }
-
@@ -15,7 +15,7 @@ fn lerp(a : f64, low : f64, high : f64) -> f64 {
 }
 
 pub struct GaussianMixtureClusterer {
-    gaussian_mixture : GaussianMixture<2>,
+    gaussian_mixture : GaussianMixture,
 
     sample_values : Vec<Vector<2>>,
 
@@ -23,10 +23,10 @@ pub struct GaussianMixtureClusterer {
     cluster_means       : Vec<Vector<2>>,
     cluster_covariances : Vec<Matrix<2>>,
 
-    priors               : Vec<f64>,
-    likelihoods          : Vec<f64>,
-    marginal_likelihoods : Vec<f64>,
-    posteriors           : Vec<f64>,
+    priors                   : Vec<f64>,
+    log_likelihoods          : Vec<f64>,
+    log_marginal_likelihoods : Vec<f64>,
+    posteriors               : Vec<f64>,
 }
 
 impl GaussianMixtureClusterer {
@@ -35,7 +35,7 @@ impl GaussianMixtureClusterer {
 
         let sample_values = samples;
         let (cluster_weights, cluster_means, cluster_covariances) = gaussian_mixture.init(&sample_values, ClusterInit::KMeanPlusPlus, &mut thread_rng());
-        let (priors, likelihoods, marginal_likelihoods, posteriors) = gaussian_mixture.e_step(&sample_values, &cluster_weights, &cluster_means, &cluster_covariances);
+        let (priors, log_likelihoods, log_marginal_likelihoods, posteriors, _) = gaussian_mixture.e_step(&sample_values, &cluster_weights, &cluster_means, &cluster_covariances);
 
         Box::new(Self {
             gaussian_mixture,
@@ -47,8 +47,8 @@ impl GaussianMixtureClusterer {
             cluster_covariances,
 
             priors,
-            likelihoods,
-            marginal_likelihoods,
+            log_likelihoods,
+            log_marginal_likelihoods,
             posteriors,
         })
     }
@@ -60,15 +60,15 @@ impl Clusterer for GaussianMixtureClusterer {
     }
 
     fn update(&mut self) {
-        let (cluster_weights, cluster_means, cluster_covariances) = self.gaussian_mixture.m_step(&self.sample_values, &self.priors, &self.likelihoods, &self.marginal_likelihoods, &self.posteriors);
+        let (cluster_weights, cluster_means, cluster_covariances) = self.gaussian_mixture.m_step(&self.sample_values, &self.priors, &self.log_likelihoods, &self.log_marginal_likelihoods, &self.posteriors);
         self.cluster_weights = cluster_weights;
         self.cluster_means = cluster_means;
         self.cluster_covariances = cluster_covariances;
 
-        let (priors, likelihoods, marginal_likelihoods, posteriors) = self.gaussian_mixture.e_step(&self.sample_values, &self.cluster_weights, &self.cluster_means, &self.cluster_covariances);
+        let (priors, log_likelihoods, log_marginal_likelihoods, posteriors, _) = self.gaussian_mixture.e_step(&self.sample_values, &self.cluster_weights, &self.cluster_means, &self.cluster_covariances);
         self.priors = priors;
-        self.likelihoods = likelihoods;
-        self.marginal_likelihoods = marginal_likelihoods;
+        self.log_likelihoods = log_likelihoods;
+        self.log_marginal_likelihoods = log_marginal_likelihoods;
         self.posteriors = posteriors;
 
     }
@@ -9,7 +9,7 @@ use math::prelude::*;
 use rand::prelude::*;
 
 pub struct KMeansClusterer {
-    k_means : KMeans<2>,
+    k_means : KMeans,
 
     sample_values : Vec<Vector<2>>,
     sample_labels : Vec<usize>,
@@ -36,7 +36,7 @@ impl Clusterer for KMeansClusterer {
     }
 
     fn update(&mut self) {
-        let (cluster_means,) = self.k_means.m_step(&self.sample_values, &self.sample_labels, &self.sample_errors);
+        let (cluster_means,) = self.k_means.m_step(&self.sample_values, &self.sample_labels, &self.sample_errors, &self.cluster_means);
         self.cluster_means = cluster_means;
 
         let (sample_labels, sample_errors) = self.k_means.e_step(&self.sample_values, &self.cluster_means);
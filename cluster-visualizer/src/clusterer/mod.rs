@@ -3,6 +3,7 @@ pub mod k_means;
 pub mod gaussian_mixture;
 pub mod affinity_propagation;
 pub mod dbscan;
+pub mod optics;
 
 pub mod slink;
 pub mod clink;
@@ -13,6 +14,7 @@ pub use k_means::KMeansClusterer;
 pub use gaussian_mixture::GaussianMixtureClusterer;
 pub use affinity_propagation::AffinityPropagationClusterer;
 pub use dbscan::DbscanClusterer;
+pub use optics::OpticsClusterer;
 
 pub use slink::SlinkClusterer;
 pub use clink::ClinkClusterer;
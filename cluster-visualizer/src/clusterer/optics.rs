@@ -0,0 +1,103 @@
+use super::Render;
+use super::Clusterer;
+use super::pick_color;
+
+use cluster::misc::optics::Optics;
+use cluster::misc::optics::OpticsResult;
+
+use math::prelude::*;
+
+/// Number of epsilon values stepped through before returning to xi extraction.
+const EPSILON_STEPS : usize = 10;
+
+/// Render samples in the left half and the reachability plot in the right half.
+///
+/// Initially, clusters are extracted by the xi method. Each update switches to DBSCAN extraction
+/// with an increasing epsilon, which is shown as a horizontal line in the reachability plot.
+pub struct OpticsClusterer {
+    samples : Vec<Vector<2>>,
+    result : OpticsResult,
+    xi : f64,
+
+    epsilon_step : usize,
+    max_reachability_distance : f64,
+
+    sample_labels : Vec<Option<usize>>,
+    cluster_count : usize,
+}
+
+impl OpticsClusterer {
+    pub fn new(samples : Vec<Vector<2>>, min_pts : usize, xi : f64) -> Box<Self> {
+        let result = Optics::new(min_pts).run(&samples);
+        let max_reachability_distance = result.reachability_distances.iter().copied().filter(|distance| distance.is_finite()).fold(0.0, f64::max);
+        let mut clusterer = Box::new(Self { samples, result, xi, epsilon_step : 0, max_reachability_distance, sample_labels : Vec::new(), cluster_count : 0 });
+        clusterer.extract();
+        clusterer
+    }
+
+    fn epsilon(&self) -> Option<f64> {
+        (self.epsilon_step != 0).then(|| self.max_reachability_distance * self.epsilon_step as f64 / EPSILON_STEPS as f64)
+    }
+
+    fn extract(&mut self) {
+        match self.epsilon() {
+            Some(epsilon) => {
+                let result = self.result.extract_dbscan(epsilon);
+                self.sample_labels = result.sample_labels.iter().map(|label| label.cluster()).collect();
+                self.cluster_count = result.cluster_count();
+            }
+            None => {
+                let (_, sample_labels) = self.result.extract_xi(self.xi, self.result.min_pts);
+                self.cluster_count = sample_labels.iter().flatten().map(|&label| label + 1).max().unwrap_or(0);
+                self.sample_labels = sample_labels;
+            }
+        }
+    }
+
+    fn color(&self, label : Option<usize>) -> (u8, u8, u8) {
+        match label {
+            Some(label) => pick_color(label as f64 / self.cluster_count as f64),
+            None => (128, 128, 128),
+        }
+    }
+}
+
+impl Clusterer for OpticsClusterer {
+    fn into_raw(self : Box<Self>) -> Vec<Vector<2>> {
+        self.samples
+    }
+
+    fn update(&mut self) {
+        self.epsilon_step = (self.epsilon_step + 1) % (EPSILON_STEPS + 1);
+        self.extract();
+    }
+
+    fn render(&self, mut render : Render<'_>) {
+        // Scatter
+        for (&sample, &label) in std::iter::zip(&self.samples, &self.sample_labels) {
+            let (r, g, b) = self.color(label);
+            render.draw_point(r, g, b, sample[0] * 0.5, sample[1], 5.0);
+        }
+
+        // Reachability plot, with undefined reachability distances drawn at full height.
+        const LEFT : f64 = 0.52;
+        const RIGHT : f64 = 0.98;
+        const TOP : f64 = 0.05;
+        const BOTTOM : f64 = 0.95;
+
+        let sample_count = self.result.ordering.len();
+        let scale = if self.max_reachability_distance > 0.0 { (BOTTOM - TOP) / self.max_reachability_distance } else { 0.0 };
+        for (position, &sample_index) in self.result.ordering.iter().enumerate() {
+            let x = LEFT + (RIGHT - LEFT) * (position as f64 + 0.5) / sample_count as f64;
+            let distance = self.result.reachability_distances[sample_index];
+            let y = if distance.is_finite() { BOTTOM - distance * scale } else { TOP };
+            let (r, g, b) = self.color(self.sample_labels[sample_index]);
+            render.draw_line(r, g, b, x, BOTTOM, x, y);
+        }
+
+        if let Some(epsilon) = self.epsilon() {
+            let y = BOTTOM - epsilon * scale;
+            render.draw_line(255, 255, 255, LEFT, y, RIGHT, y);
+        }
+    }
+}
@@ -15,6 +15,7 @@ use clusterer::KMeansClusterer;
 use clusterer::GaussianMixtureClusterer;
 use clusterer::AffinityPropagationClusterer;
 use clusterer::DbscanClusterer;
+use clusterer::OpticsClusterer;
 use clusterer::SlinkClusterer;
 use clusterer::ClinkClusterer;
 use clusterer::AgglomerativeClusterer;
@@ -38,13 +39,14 @@ pub fn main() {
 
     eprintln!("  Press C-p for affinity propagation");
     eprintln!("  Press C-d for dbscan(Density-based spatial clustering of applications with noise)");
+    eprintln!("  Press C-o for OPTICS with reachability plot (step to sweep epsilon)");
 
     eprintln!("  Press C-S for single linkage clustering.");
     eprintln!("  Press C-C for complete linkage clustering.");
     eprintln!("  Press C-A for average linkage clustering.");
 
     eprintln!("  Press C-s for SLINK clustering.");
-    eprintln!("  Press C-c for CLINK clustering (The results look bad for whatever reason!!!).");
+    eprintln!("  Press C-c for CLINK clustering.");
 
     eprintln!("  Press t to to start/stop stepping through the algorithm automatically");
     eprintln!("  Press s to step through the algorithm.");
@@ -83,8 +85,9 @@ pub fn main() {
 
                 Event::KeyDown { keymod, keycode : Some(Keycode::K), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = KMeansClusterer::new(clusterer.into_raw(), 10),
                 Event::KeyDown { keymod, keycode : Some(Keycode::G), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = GaussianMixtureClusterer::new(clusterer.into_raw(), 10),
-                Event::KeyDown { keymod, keycode : Some(Keycode::A), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = AffinityPropagationClusterer::new(clusterer.into_raw(), -0.1, 0.7),
+                Event::KeyDown { keymod, keycode : Some(Keycode::A), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = AffinityPropagationClusterer::new(clusterer.into_raw(), 0.7),
                 Event::KeyDown { keymod, keycode : Some(Keycode::D), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = DbscanClusterer::new(clusterer.into_raw(), 0.03, 8),
+                Event::KeyDown { keymod, keycode : Some(Keycode::O), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = OpticsClusterer::new(clusterer.into_raw(), 8, 0.05),
 
                 Event::KeyDown { keymod, keycode : Some(Keycode::S), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = SlinkClusterer::new(clusterer.into_raw(), 10),
                 Event::KeyDown { keymod, keycode : Some(Keycode::C), .. } if keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD) => clusterer = ClinkClusterer::new(clusterer.into_raw(), 10),
@@ -21,6 +21,7 @@ impl<'a> Render<'a> {
         self.canvas.draw_point(((x / size * self.width as f64) as i32, (y / size * self.height as f64) as i32)).unwrap();
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_line(&mut self, r : u8, g : u8, b : u8, x1 : f64, y1 : f64, x2 : f64, y2 : f64) {
         self.canvas.set_draw_color(Color::RGB(r, g, b));
         self.canvas.set_scale(1.0, 1.0).unwrap();
@@ -30,13 +31,14 @@ impl<'a> Render<'a> {
         ).unwrap();
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_ellipse_scaled(&mut self, r : u8, g : u8, b : u8, x : f64, y : f64, rx : f64, ry : f64, angle : f64, sx : f64, sy : f64) {
         self.canvas.set_draw_color(Color::RGB(r, g, b));
         self.canvas.set_scale(1.0, 1.0).unwrap();
 
         const STEP: usize = 200;
         for step in 0..STEP {
-            let angle0 = ((step + 0) as f64 / STEP as f64) * 2.0 * std::f64::consts::PI;
+            let angle0 = (step as f64 / STEP as f64) * 2.0 * std::f64::consts::PI;
             let angle1 = ((step + 1) as f64 / STEP as f64) * 2.0 * std::f64::consts::PI;
 
             let x0 = rx * angle0.cos();
@@ -0,0 +1,66 @@
+use cluster::expectation_maximization::init::ClusterInit;
+use cluster::expectation_maximization::k_means::*;
+
+use math::prelude::*;
+use rand::prelude::*;
+
+use criterion::*;
+
+/// Pixels of a synthetic 320x240 image, drawn around a palette of 64 random colors.
+fn image_samples() -> Vec<Vector<3>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let palette = (0..64).map(|_| [0; 3].map(|_| rng.gen_range(0.0..256.0f64))).collect::<Vec<_>>();
+    (0..320 * 240)
+        .map(|_| {
+            let color = palette.choose(&mut rng).unwrap();
+            Vector::from_array(color.map(|component| (component + rng.gen_range(-16.0..16.0f64)).clamp(0.0, 255.0)))
+        })
+        .collect()
+}
+
+/// Samples of dimension 64 drawn around 64 random centers.
+fn high_dimension_samples() -> Vec<DVector> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let centers = (0..64).map(|_| DVector::from_fn(64, |_| rng.gen_range(0.0..1.0))).collect::<Vec<_>>();
+    (0..10000)
+        .map(|_| {
+            let center = centers.choose(&mut rng).unwrap();
+            DVector::from_fn(64, |index| center[index] + rng.gen_range(-0.1..0.1))
+        })
+        .collect()
+}
+
+fn bench_k_means(c : &mut Criterion) {
+    let samples = image_samples();
+
+    let mut group = c.benchmark_group("k_means");
+    group.sample_size(10);
+    for cluster_count in [8, 32, 128] {
+        for algorithm in [KMeansAlgorithm::Lloyd, KMeansAlgorithm::Hamerly, KMeansAlgorithm::Elkan] {
+            let k_means = KMeans { algorithm, ..KMeans::new(samples.len(), cluster_count) };
+            group.bench_with_input(BenchmarkId::new(format!("{algorithm:?}"), cluster_count), &k_means, |b, k_means| {
+                b.iter(|| k_means.run(&samples, ClusterInit::KMeanPlusPlus, &mut StdRng::seed_from_u64(0)))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_k_means_high_dimension(c : &mut Criterion) {
+    let samples = high_dimension_samples();
+
+    let mut group = c.benchmark_group("k_means_high_dimension");
+    group.sample_size(10);
+    for cluster_count in [8, 32, 128] {
+        for algorithm in [KMeansAlgorithm::Lloyd, KMeansAlgorithm::Hamerly, KMeansAlgorithm::Elkan] {
+            let k_means = KMeans { algorithm, ..KMeans::new(samples.len(), cluster_count) };
+            group.bench_with_input(BenchmarkId::new(format!("{algorithm:?}"), cluster_count), &k_means, |b, k_means| {
+                b.iter(|| k_means.run(&samples, ClusterInit::KMeanPlusPlus, &mut StdRng::seed_from_u64(0)))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_k_means, bench_k_means_high_dimension);
+criterion_main!(benches);
@@ -0,0 +1,178 @@
+//! Precomputed pairwise distances.
+//!
+//! Algorithms that only look at distances between samples, such as hierarchical clustering,
+//! affinity propagation and DBSCAN, can consume a [DistanceMatrix] instead of samples, so that any
+//! kind of data with any notion of distance, such as strings with edit distance, can be clustered
+//! in the same way.
+
+use crate::metric::Metric;
+
+use rayon::prelude::*;
+
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+
+/// Symmetric matrix of distances between samples with zero diagonal.
+///
+/// Only the upper triangle is stored, in row-major order, which is known as condensed form. This
+/// is the same layout as SciPy's `pdist`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMatrix {
+    sample_count : usize,
+    distances : Vec<f64>,
+}
+
+impl DistanceMatrix {
+    /// Construct distance matrix from distances in condensed form.
+    pub fn from_condensed(sample_count : usize, distances : Vec<f64>) -> Self {
+        assert_eq!(distances.len(), sample_count * sample_count.saturating_sub(1) / 2);
+        Self { sample_count, distances }
+    }
+
+    /// Construct distance matrix by evaluating distance(i, j) for every i < j, in parallel on the
+    /// global thread pool.
+    pub fn from_fn<F>(sample_count : usize, distance : F) -> Self
+    where
+        F: Fn(usize, usize) -> f64 + Sync,
+    {
+        let distances = (0..sample_count)
+            .into_par_iter()
+            .flat_map_iter(|i| (i+1..sample_count).map(move |j| (i, j)))
+            .map(|(i, j)| distance(i, j))
+            .collect();
+        Self { sample_count, distances }
+    }
+
+    /// Construct distance matrix by evaluating distance between every pair of samples, in
+    /// parallel on the global thread pool.
+    pub fn from_samples<T, F>(samples : &[T], distance : F) -> Self
+    where
+        T: Sync,
+        F: Fn(&T, &T) -> f64 + Sync,
+    {
+        Self::from_fn(samples.len(), |i, j| distance(&samples[i], &samples[j]))
+    }
+
+    /// Construct distance matrix by evaluating metric between every pair of samples, in parallel
+    /// on the global thread pool.
+    pub fn from_metric<P: Sync, M: Metric<P>>(samples : &[P], metric : &M) -> Self {
+        Self::from_samples(samples, |a, b| metric.distance(a, b))
+    }
+
+    /// Read distance matrix in text format.
+    ///
+    /// Each non-empty line is a row of the full square matrix, with values separated by
+    /// whitespace or commas. Only the upper triangle is used.
+    pub fn read<R: BufRead>(reader : R) -> std::io::Result<Self> {
+        let invalid_data = |message : String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+        let mut rows = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let row = line
+                .split(|c : char| c.is_whitespace() || c == ',')
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse::<f64>().map_err(|err| invalid_data(format!("invalid distance {value:?}: {err}"))))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            rows.push(row);
+        }
+
+        let sample_count = rows.len();
+        let mut distances = Vec::with_capacity(sample_count * sample_count.saturating_sub(1) / 2);
+        for (i, row) in rows.into_iter().enumerate() {
+            if row.len() != sample_count {
+                return Err(invalid_data(format!("row {i} has {} values but there are {sample_count} rows", row.len())));
+            }
+            distances.extend_from_slice(&row[i+1..]);
+        }
+        Ok(Self { sample_count, distances })
+    }
+
+    /// Load distance matrix in the text format of [Self::read] from file.
+    pub fn load<P: AsRef<Path>>(path : P) -> std::io::Result<Self> {
+        Self::read(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Write distance matrix in the text format of [Self::read].
+    pub fn write<W: Write>(&self, mut writer : W) -> std::io::Result<()> {
+        for i in 0..self.sample_count {
+            for j in 0..self.sample_count {
+                if j != 0 {
+                    write!(writer, " ")?;
+                }
+                write!(writer, "{}", self.get(i, j))?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    /// Distances in condensed form.
+    pub fn condensed(&self) -> &[f64] {
+        &self.distances
+    }
+
+    /// Distance between sample i and sample j.
+    pub fn get(&self, i : usize, j : usize) -> f64 {
+        assert!(i < self.sample_count && j < self.sample_count);
+        match i.cmp(&j) {
+            std::cmp::Ordering::Less    => self.distances[self.condensed_index(i, j)],
+            std::cmp::Ordering::Greater => self.distances[self.condensed_index(j, i)],
+            std::cmp::Ordering::Equal   => 0.0,
+        }
+    }
+
+    /// Overwrite distance between distinct samples i and j.
+    pub(crate) fn set(&mut self, i : usize, j : usize, distance : f64) {
+        assert!(i != j && i < self.sample_count && j < self.sample_count);
+        let index = if i < j { self.condensed_index(i, j) } else { self.condensed_index(j, i) };
+        self.distances[index] = distance;
+    }
+
+    /// Distance as a closure over sample indices, for algorithms taking one.
+    pub fn dissimilarity(&self) -> impl Fn(usize, usize) -> f64 + Copy + '_ {
+        |i, j| self.get(i, j)
+    }
+
+    fn condensed_index(&self, i : usize, j : usize) -> usize {
+        // Rows before i contain (n-1) + (n-2) + ... + (n-i) entries.
+        i * (2 * self.sample_count - i - 1) / 2 + (j - i - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::levenshtein;
+
+    #[test]
+    fn test_distance_matrix() {
+        let samples = ["kitten", "sitting", "mitten", "fitting"];
+        let distance_matrix = DistanceMatrix::from_samples(&samples, |a, b| levenshtein(a, b));
+        assert_eq!(distance_matrix.condensed().len(), 6);
+        for i in 0..samples.len() {
+            for j in 0..samples.len() {
+                assert_eq!(distance_matrix.get(i, j), levenshtein(samples[i], samples[j]));
+            }
+        }
+
+        let mut buffer = Vec::new();
+        distance_matrix.write(&mut buffer).unwrap();
+        assert_eq!(DistanceMatrix::read(buffer.as_slice()).unwrap(), distance_matrix);
+
+        let text = "0, 1, 2\n1, 0, 3\n\n2 3 0\n";
+        assert_eq!(DistanceMatrix::read(text.as_bytes()).unwrap(), DistanceMatrix::from_condensed(3, vec![1.0, 2.0, 3.0]));
+        assert!(DistanceMatrix::read("0 1\n1 0 2\n".as_bytes()).is_err());
+        assert!(DistanceMatrix::read("0 x\nx 0\n".as_bytes()).is_err());
+        assert_eq!(DistanceMatrix::read("".as_bytes()).unwrap().sample_count(), 0);
+    }
+}
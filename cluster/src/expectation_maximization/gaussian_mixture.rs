@@ -1,97 +1,182 @@
 use super::init::ClusterInit;
+use super::stopping_criteria::*;
+use super::restarts::Restarts;
 
 use math::prelude::*;
 use rand::prelude::*;
 
+use clap::ValueEnum;
+
+/// Constraint on the covariance matrices of a Gaussian mixture model.
+///
+/// More constrained models have fewer parameters, which make them faster to fit and less prone
+/// to degenerate covariance matrices, at the cost of expressiveness.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum CovarianceType {
+    /// Each cluster has its own general covariance matrix.
+    #[default]
+    Full,
+    /// Each cluster has its own diagonal covariance matrix.
+    Diagonal,
+    /// Each cluster has its own single variance shared by all dimensions.
+    Spherical,
+    /// All clusters share the same general covariance matrix.
+    Tied,
+}
+
 /// Implementation of Gaussian mixture model Clustering algorithm.
 #[derive(Debug, Copy, Clone)]
-pub struct GaussianMixture<const N: usize> {
+pub struct GaussianMixture {
     pub sample_count : usize,
     pub cluster_count : usize,
+    pub covariance_type : CovarianceType,
+    /// Non-negative value added to the diagonal of every covariance matrix estimate.
+    ///
+    /// This ensures that covariance matrices stay positive-definite even if a cluster collapses
+    /// onto a single sample or samples lie in a lower dimensional subspace.
+    pub covariance_regularization : f64,
+    pub stopping_criteria : StoppingCriteria,
 }
 
-impl<const N: usize> GaussianMixture<N> {
+impl GaussianMixture {
     /// Constructor.
+    ///
+    /// Full covariance matrices, a covariance regularization of 1e-6 and the default
+    /// [StoppingCriteria] are used by default.
     pub fn new(sample_count : usize, cluster_count : usize) -> Self {
-        Self { sample_count, cluster_count, }
+        Self { sample_count, cluster_count, covariance_type : CovarianceType::Full, covariance_regularization : 1e-6, stopping_criteria : StoppingCriteria::default(), }
     }
 
     /// Gaussian mixture model initialization step.
     ///
+    /// Initial covariances are estimated by assigning each sample to its nearest initial cluster
+    /// mean. Clusters with no sample assigned, e.g. if initial cluster means coincide, get the
+    /// covariance of all samples, see [Self::responsibilities].
+    ///
     /// **Inputs**:  (sample_values) <br/>
     /// **Outputs**: (cluster_weights, cluster_means, cluster_covariances)
-    pub fn init<R>(self, sample_values : &[Vector<N>], init : ClusterInit, rng : &mut R) -> (Vec<f64>, Vec<Vector<N>>, Vec<Matrix<N>>)
+    pub fn init<P, R>(self, sample_values : &[P], init : ClusterInit, rng : &mut R) -> (Vec<f64>, Vec<P>, Vec<P::Matrix>)
     where
-        R: Rng
+        P: Point,
+        R: Rng,
     {
         assert_eq!(self.sample_count, sample_values.len());
 
         let cluster_weights = vec![1.0 / self.cluster_count as f64; self.cluster_count];
         let cluster_means = init.init(rng, sample_values, self.cluster_count);
-        let cluster_covariances = vec![Matrix::one() * 0.01; self.cluster_count];
+
+        let mut posteriors = vec![0.0; self.sample_count * self.cluster_count];
+        for sample_index in 0..self.sample_count {
+            let cluster_index = (0..self.cluster_count)
+                .min_by(|&a, &b| f64::total_cmp(&sample_values[sample_index].squared_distance(&cluster_means[a]), &sample_values[sample_index].squared_distance(&cluster_means[b])))
+                .unwrap();
+            posteriors[cluster_index * self.sample_count + sample_index] = 1.0;
+        }
+        let cluster_covariances = self.estimate_covariances(sample_values, &posteriors, &cluster_means);
 
         (cluster_weights, cluster_means, cluster_covariances)
     }
 
     /// Gaussian mixture model expectation step(Kinda).
     ///
+    /// Everything is computed in log space and posteriors are normalized with log-sum-exp, so that
+    /// samples far away from all clusters neither underflow to zero likelihood nor distort the
+    /// posteriors of other samples. The last output is the total log-likelihood of the data
+    /// sum(ln(P(X))), which is non-decreasing across iterations.
+    ///
     /// **Inputs**:  (sample_values, cluster_weights, cluster_means, cluster_covariances) <br/>
-    /// **Outputs**: (priors, likelihoods, marginal_likelihoods, posteriors) i.e. All your Bayesian goodies
-    pub fn e_step(self, sample_values : &[Vector<N>], cluster_weights : &[f64], cluster_means : &[Vector<N>], cluster_covariances : &[Matrix<N>]) -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>) {
+    /// **Outputs**: (priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood) i.e. All your Bayesian goodies
+    pub fn e_step<P: Point>(self, sample_values : &[P], cluster_weights : &[f64], cluster_means : &[P], cluster_covariances : &[P::Matrix]) -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>, f64) {
         assert_eq!(self.sample_count, sample_values.len());
         assert_eq!(self.cluster_count, cluster_weights.len());
         assert_eq!(self.cluster_count, cluster_means.len());
         assert_eq!(self.cluster_count, cluster_covariances.len());
 
         // 1: Compute priors P(C) from current estimate of model parameters.
-        let mut priors = vec![Default::default(); self.sample_count];
+        let mut priors = vec![Default::default(); self.cluster_count];
         for cluster_index in 0..self.cluster_count {
             priors[cluster_index] = cluster_weights[cluster_index];
         }
 
-        // 2: Compute likelihoods P(X|C) from current estimate of model parameters.
-        let mut likelihoods = vec![Default::default(); self.sample_count * self.cluster_count];
-        for cluster_index in 0..self.cluster_count {
-            let dist = MultivariateGaussian::new(cluster_means[cluster_index], cluster_covariances[cluster_index]);
-            for sample_index in 0..self.sample_count {
-                // Disallow zero likelihoods, sort of like add one smoothing I guess? This prevent
-                // division by zero down the road. In particular, we do not want zero marginal
-                // likelihood if a sample happen to be far away from all clusters.
-                likelihoods[cluster_index * self.sample_count + sample_index] = dist.sample(sample_values[sample_index]).max(1e-16);
+        // 2: Compute log likelihoods ln(P(X|C)) from current estimate of model parameters.
+        let mut log_likelihoods = vec![Default::default(); self.sample_count * self.cluster_count];
+        match self.covariance_type {
+            CovarianceType::Full => {
+                for cluster_index in 0..self.cluster_count {
+                    let dist = MultivariateGaussian::new(cluster_means[cluster_index].clone(), cluster_covariances[cluster_index].clone());
+                    for sample_index in 0..self.sample_count {
+                        log_likelihoods[cluster_index * self.sample_count + sample_index] = dist.log_pdf(&sample_values[sample_index]);
+                    }
+                }
+            }
+            CovarianceType::Tied => {
+                // All clusters share the same covariance matrix, so it is factorized only once.
+                if let (Some(mean), Some(covariance)) = (cluster_means.first(), cluster_covariances.first()) {
+                    let tied_dist = MultivariateGaussian::new(mean.clone(), covariance.clone());
+                    for cluster_index in 0..self.cluster_count {
+                        let dist = tied_dist.with_mean(cluster_means[cluster_index].clone());
+                        for sample_index in 0..self.sample_count {
+                            log_likelihoods[cluster_index * self.sample_count + sample_index] = dist.log_pdf(&sample_values[sample_index]);
+                        }
+                    }
+                }
+            }
+            CovarianceType::Diagonal | CovarianceType::Spherical => {
+                // The density factorizes over dimensions if covariance matrices are diagonal, so
+                // there is no need for any matrix decomposition.
+                for cluster_index in 0..self.cluster_count {
+                    let mean = &cluster_means[cluster_index];
+                    let covariance = &cluster_covariances[cluster_index];
+                    let dimension = mean.dimension();
+
+                    let log_normalizing_factor = -0.5 * (0..dimension).map(|j| (2.0 * std::f64::consts::PI * covariance[(j, j)]).ln()).sum::<f64>();
+                    for sample_index in 0..self.sample_count {
+                        let sample_value = &sample_values[sample_index];
+                        let mahalanobis = (0..dimension).map(|j| (sample_value[j] - mean[j]).powi(2) / covariance[(j, j)]).sum::<f64>();
+                        log_likelihoods[cluster_index * self.sample_count + sample_index] = -0.5 * mahalanobis + log_normalizing_factor;
+                    }
+                }
             }
         }
 
-        // 3: Compute marginal likelihoods P(X) = sum(P(X|C_i)P(C_i)).
-        let mut marginal_likelihoods = vec![Default::default(); self.sample_count];
+        // 3: Compute log marginal likelihoods ln(P(X)) = ln(sum(exp(ln(P(X|C_i)) + ln(P(C_i))))).
+        let mut log_marginal_likelihoods = vec![Default::default(); self.sample_count];
         for sample_index in 0..self.sample_count {
-            marginal_likelihoods[sample_index] = 0.0;
-            for cluster_index in 0..self.cluster_count {
-                marginal_likelihoods[sample_index] += likelihoods[cluster_index * self.sample_count + sample_index] * priors[cluster_index];
-            }
+            log_marginal_likelihoods[sample_index] = (0..self.cluster_count)
+                .map(|cluster_index| log_likelihoods[cluster_index * self.sample_count + sample_index] + priors[cluster_index].ln())
+                .log_sum_exp();
         }
 
-        // 4: Compute posteriors P(C|X) = P(X|C)*P(C)/P(X)
+        // 4: Compute posteriors P(C|X) = exp(ln(P(X|C)) + ln(P(C)) - ln(P(X)))
         let mut posteriors = vec![Default::default(); self.sample_count * self.cluster_count];
         for cluster_index in 0..self.cluster_count {
             for sample_index in 0..self.sample_count {
-                posteriors[cluster_index * self.sample_count + sample_index] = likelihoods[cluster_index * self.sample_count + sample_index] * priors[cluster_index] / marginal_likelihoods[sample_index]; // <- Aforementioned potential Division By Zero
+                posteriors[cluster_index * self.sample_count + sample_index] = (log_likelihoods[cluster_index * self.sample_count + sample_index] + priors[cluster_index].ln() - log_marginal_likelihoods[sample_index]).exp();
             }
         }
 
-        (priors, likelihoods, marginal_likelihoods, posteriors)
+        // 5: Compute total log likelihood of data.
+        let data_log_likelihood = log_marginal_likelihoods.iter().sum::<f64>();
+
+        (priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood)
     }
 
     /// Gaussian mixture model maximization step(Kinda).
     ///
-    /// **Inputs**:  (sample_values, priors, likelihoods, marginal_likelihoods, posteriors) <br/>
+    /// A cluster which no sample is responsible for at all gets the mean and covariance of all
+    /// samples, see [Self::responsibilities], and keeps a weight of zero.
+    ///
+    /// **Inputs**:  (sample_values, priors, log_likelihoods, log_marginal_likelihoods, posteriors) <br/>
     /// **Outputs**: (cluster_weights, cluster_means, cluster_covariances)
-    pub fn m_step(self, sample_values : &[Vector<N>], priors : &[f64], likelihoods : &[f64], marginal_likelihoods : &[f64], posteriors : &[f64]) -> (Vec<f64>, Vec<Vector<N>>, Vec<Matrix<N>>) {
+    pub fn m_step<P: Point>(self, sample_values : &[P], priors : &[f64], log_likelihoods : &[f64], log_marginal_likelihoods : &[f64], posteriors : &[f64]) -> (Vec<f64>, Vec<P>, Vec<P::Matrix>) {
         assert_eq!(self.sample_count, sample_values.len());
-        assert_eq!(self.sample_count, priors.len());
-        assert_eq!(self.sample_count * self.cluster_count, likelihoods.len());
-        assert_eq!(self.sample_count, marginal_likelihoods.len());
+        assert_eq!(self.cluster_count, priors.len());
+        assert_eq!(self.sample_count * self.cluster_count, log_likelihoods.len());
+        assert_eq!(self.sample_count, log_marginal_likelihoods.len());
         assert_eq!(self.sample_count * self.cluster_count, posteriors.len());
 
+        let dimension = sample_values.first().map_or(0, P::dimension);
+
         // 1: MLE estimate of cluster weights: weight(C) = sum(P(C|X)) / sample_count
         let mut cluster_weights = vec![Default::default(); self.cluster_count];
         for cluster_index in 0..self.cluster_count {
@@ -103,71 +188,161 @@ impl<const N: usize> GaussianMixture<N> {
         }
 
         // 2: MLE estimate of cluster means: mean(C) = weighted_average(X, P(C|X))
-        let mut cluster_means = vec![Default::default(); self.cluster_count];
+        let mut cluster_means = vec![P::zero(dimension); self.cluster_count];
         for cluster_index in 0..self.cluster_count {
-            let mut total = Vector::zero();
+            let responsibilities = self.responsibilities(posteriors, cluster_index);
+            let mut total = P::zero(dimension);
             let mut weight = 0.0;
             for sample_index in 0..self.sample_count {
-                total  += sample_values[sample_index] * posteriors[cluster_index * self.sample_count + sample_index];
-                weight +=                               posteriors[cluster_index * self.sample_count + sample_index];
+                total  += sample_values[sample_index].clone() * responsibilities[sample_index];
+                weight +=                                       responsibilities[sample_index];
             }
             cluster_means[cluster_index] = total / weight;
         }
 
-        // 3: MLE estimate of cluster covariances: covariance(C) = weighted_average((X-mean(C))(X-mean(C))^T, P(C|X)) * sample_count / (sample_count - 1) (With Bessel's correction)
-        let mut cluster_covariances = vec![Default::default(); self.cluster_count];
-        for cluster_index in 0..self.cluster_count {
-            let mut total = Matrix::zero();
-            let mut weight = 0.0;
-            for sample_index in 0..self.sample_count {
-                total  += (sample_values[sample_index] - cluster_means[cluster_index]).outer_product(sample_values[sample_index] - cluster_means[cluster_index]) * posteriors[cluster_index * self.sample_count + sample_index];
-                weight +=                                                                                                                                          posteriors[cluster_index * self.sample_count + sample_index];
-            }
-            cluster_covariances[cluster_index] = total / weight * self.sample_count as f64 / (self.sample_count - 1) as f64;
-        }
+        // 3: MLE estimate of cluster covariances.
+        let cluster_covariances = self.estimate_covariances(sample_values, posteriors, &cluster_means);
 
         (cluster_weights, cluster_means, cluster_covariances)
     }
 
+    /// Responsibilities of cluster for samples, which are posteriors P(C|X).
+    ///
+    /// If the total responsibility is zero, which happens if the cluster coincides with another
+    /// cluster on coincident samples or is too far away from all samples, weighted averages would
+    /// be 0/0. Every sample is then equally responsible instead.
+    fn responsibilities(self, posteriors : &[f64], cluster_index : usize) -> std::borrow::Cow<'_, [f64]> {
+        let responsibilities = &posteriors[cluster_index * self.sample_count..(cluster_index + 1) * self.sample_count];
+        if responsibilities.iter().sum::<f64>() == 0.0 {
+            std::borrow::Cow::Owned(vec![1.0; self.sample_count])
+        } else {
+            std::borrow::Cow::Borrowed(responsibilities)
+        }
+    }
+
+    /// Estimate cluster covariances subject to [Self::covariance_type].
+    ///
+    /// Covariances are weighted averages of (X-mean(C))(X-mean(C))^T by P(C|X), with Bessel's
+    /// correction, restricted to the diagonal for diagonal and spherical covariance types. Finally,
+    /// [Self::covariance_regularization] is added to the diagonal.
+    fn estimate_covariances<P: Point>(self, sample_values : &[P], posteriors : &[f64], cluster_means : &[P]) -> Vec<P::Matrix> {
+        let dimension = sample_values.first().map_or(0, P::dimension);
+        // Bessel's correction is undefined for a single sample.
+        let correction = if self.sample_count > 1 { self.sample_count as f64 / (self.sample_count - 1) as f64 } else { 1.0 };
+        let regularization = P::Matrix::one(dimension) * self.covariance_regularization;
+
+        match self.covariance_type {
+            // covariance(C) = weighted_average((X-mean(C))(X-mean(C))^T, P(C|X))
+            CovarianceType::Full => (0..self.cluster_count).map(|cluster_index| {
+                let responsibilities = self.responsibilities(posteriors, cluster_index);
+                let mut total = P::Matrix::zero(dimension);
+                let mut weight = 0.0;
+                for sample_index in 0..self.sample_count {
+                    let displacement = sample_values[sample_index].clone() - cluster_means[cluster_index].clone();
+                    total  += displacement.outer(&displacement) * responsibilities[sample_index];
+                    weight +=                                     responsibilities[sample_index];
+                }
+                total / weight * correction + regularization.clone()
+            }).collect(),
+            // covariance(C)_jj = weighted_average((X_j-mean(C)_j)^2, P(C|X))
+            // covariance(C)    = mean(covariance(C)_jj) * I (Spherical)
+            CovarianceType::Diagonal | CovarianceType::Spherical => (0..self.cluster_count).map(|cluster_index| {
+                let responsibilities = self.responsibilities(posteriors, cluster_index);
+                let mut total = P::zero(dimension);
+                let mut weight = 0.0;
+                for sample_index in 0..self.sample_count {
+                    let responsibility = responsibilities[sample_index];
+                    for j in 0..dimension {
+                        total[j] += (sample_values[sample_index][j] - cluster_means[cluster_index][j]).powi(2) * responsibility;
+                    }
+                    weight += responsibility;
+                }
+
+                let variances = total / weight * correction;
+                let covariance = if self.covariance_type == CovarianceType::Diagonal {
+                    P::Matrix::from_fn(dimension, |(j, i)| if j == i { variances[j] } else { 0.0 })
+                } else {
+                    P::Matrix::one(dimension) * (variances.as_slice().iter().sum::<f64>() / dimension as f64)
+                };
+                covariance + regularization.clone()
+            }).collect(),
+            // covariance = sum(P(C|X)(X-mean(C))(X-mean(C))^T) / sample_count
+            CovarianceType::Tied => {
+                let mut total = P::Matrix::zero(dimension);
+                for cluster_index in 0..self.cluster_count {
+                    for sample_index in 0..self.sample_count {
+                        let displacement = sample_values[sample_index].clone() - cluster_means[cluster_index].clone();
+                        total += displacement.outer(&displacement) * posteriors[cluster_index * self.sample_count + sample_index];
+                    }
+                }
+                vec![total / self.sample_count as f64 * correction + regularization; self.cluster_count]
+            }
+        }
+    }
+
     /// Gaussian mixture model algorithm.
     ///
+    /// Terminate according to [Self::stopping_criteria], where the objective is
+    /// data_log_likelihood and the parameters are cluster_weights, cluster_means and
+    /// cluster_covariances.
+    ///
     /// **Inputs**:  (sample_values) <br/>
-    /// **Outputs**: (cluster_weights, cluster_means, cluster_covariances, priors, likelihoods, marginal_likelihoods, posteriors)
-    pub fn run<R>(self, sample_values : &[Vector<N>], init : ClusterInit, rng : &mut R) -> (Vec<f64>, Vec<Vector<N>>, Vec<Matrix<N>>, Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>)
+    /// **Outputs**: (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, report)
+    pub fn run<P, R>(self, sample_values : &[P], init : ClusterInit, rng : &mut R) -> (Vec<f64>, Vec<P>, Vec<P::Matrix>, Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>, f64, ConvergenceReport)
     where
-        R: Rng
+        P: Point,
+        R: Rng,
     {
-        let (cluster_weights, cluster_means, cluster_covariances) = self.init(sample_values, init, rng);
+        let (mut cluster_weights, mut cluster_means, mut cluster_covariances) = self.init(sample_values, init, rng);
 
-        let (mut priors, mut likelihoods, mut marginal_likelihoods, mut posteriors) = self.e_step(sample_values, &cluster_weights, &cluster_means, &cluster_covariances);
-        let (mut cluster_weights, mut cluster_means, mut cluster_covariances)       = self.m_step(sample_values, &priors, &likelihoods, &marginal_likelihoods, &posteriors);
+        let (mut priors, mut log_likelihoods, mut log_marginal_likelihoods, mut posteriors, mut data_log_likelihood) = self.e_step(sample_values, &cluster_weights, &cluster_means, &cluster_covariances);
+        let mut iterations = 0;
         loop {
-            let (new_priors, new_likelihoods, new_marginal_likelihoods, new_posteriors) = self.e_step(sample_values, &cluster_weights, &cluster_means, &cluster_covariances);
-            let terminate = false;
-            priors = new_priors;
-            likelihoods = new_likelihoods;
-            marginal_likelihoods = new_marginal_likelihoods;
-            posteriors = new_posteriors;
-            if terminate {
-                break
-            }
-
-            let (new_cluster_weights, new_cluster_means, new_cluster_covariances) = self.m_step(sample_values, &priors, &likelihoods, &marginal_likelihoods, &posteriors);
-            let terminate = {
+            let (new_cluster_weights, new_cluster_means, new_cluster_covariances) = self.m_step(sample_values, &priors, &log_likelihoods, &log_marginal_likelihoods, &posteriors);
+            let parameter_change = {
                 let update_cluster_weights     = new_cluster_weights    .iter().copied().zip(cluster_weights    .iter().copied()).map(|(x, y)| x - y).mse();
-                let update_cluster_means       = new_cluster_means      .iter().copied().zip(cluster_means      .iter().copied()).map(|(x, y)| x - y).mse();
-                let update_cluster_covariances = new_cluster_covariances.iter().copied().zip(cluster_covariances.iter().copied()).map(|(x, y)| x - y).mse();
-                update_cluster_weights + update_cluster_means + update_cluster_covariances <= 1e-4
+                let update_cluster_means       = new_cluster_means      .iter().cloned().zip(cluster_means      .iter().cloned()).map(|(x, y)| x - y).mse();
+                let update_cluster_covariances = new_cluster_covariances.iter().cloned().zip(cluster_covariances.iter().cloned()).map(|(x, y)| x - y).mse();
+                update_cluster_weights + update_cluster_means + update_cluster_covariances
             };
             cluster_weights = new_cluster_weights;
             cluster_means = new_cluster_means;
             cluster_covariances = new_cluster_covariances;
-            if terminate {
-                break
+
+            let (new_priors, new_log_likelihoods, new_log_marginal_likelihoods, new_posteriors, new_data_log_likelihood) = self.e_step(sample_values, &cluster_weights, &cluster_means, &cluster_covariances);
+            let objective_change = new_data_log_likelihood - data_log_likelihood;
+            priors = new_priors;
+            log_likelihoods = new_log_likelihoods;
+            log_marginal_likelihoods = new_log_marginal_likelihoods;
+            posteriors = new_posteriors;
+            data_log_likelihood = new_data_log_likelihood;
+
+            iterations += 1;
+            if let Some(reason) = self.stopping_criteria.check(iterations, objective_change, parameter_change) {
+                let report = ConvergenceReport { iterations, reason };
+                break (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, report)
             }
         }
+    }
 
-        (cluster_weights, cluster_means, cluster_covariances, priors, likelihoods, marginal_likelihoods, posteriors)
+    /// Gaussian mixture model algorithm with multiple random initializations.
+    ///
+    /// Run the algorithm once per restart and keep the run with the highest data_log_likelihood.
+    /// The seed of the winning run is returned, see [Restarts] for how to reproduce it.
+    ///
+    /// **Inputs**:  (sample_values) <br/>
+    /// **Outputs**: (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, report, seed)
+    pub fn run_restarts<P, R>(self, sample_values : &[P], init : ClusterInit, restarts : Restarts, rng : &mut R) -> (Vec<f64>, Vec<P>, Vec<P::Matrix>, Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>, f64, ConvergenceReport, u64)
+    where
+        P: Point,
+        R: Rng,
+    {
+        let (result, seed) = restarts.best_of(rng,
+            |rng| self.run(sample_values, init, rng),
+            |result| -result.7,
+        );
+        let (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, report) = result;
+        (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, report, seed)
     }
 }
 
@@ -195,16 +370,186 @@ mod tests {
 
         let sample_count = samples.len();
         let cluster_count = 2;
-        let (cluster_weights, cluster_means, cluster_covariances, priors, likelihoods, marginal_likelihoods, posteriors) = GaussianMixture::new(sample_count, cluster_count).run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+        let (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, _) = GaussianMixture::new(sample_count, cluster_count).run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
 
         assert!(cluster_weights    .iter().copied()                                            .all(f64::is_finite));
         assert!(cluster_means      .iter().copied().map(Vector::into_array).flatten()          .all(f64::is_finite));
         assert!(cluster_covariances.iter().copied().map(Matrix::into_array).flatten().flatten().all(f64::is_finite));
 
-        assert!(likelihoods.         iter().copied().all(f64::is_finite));
-        assert!(marginal_likelihoods.iter().copied().all(f64::is_finite));
-        assert!(priors              .iter().copied().all(f64::is_finite));
-        assert!(posteriors          .iter().copied().all(f64::is_finite));
+        assert!(log_likelihoods.         iter().copied().all(f64::is_finite));
+        assert!(log_marginal_likelihoods.iter().copied().all(f64::is_finite));
+        assert!(priors                  .iter().copied().all(f64::is_finite));
+        assert!(posteriors              .iter().copied().all(f64::is_finite));
+        assert!(data_log_likelihood.is_finite());
     }
-}
 
+    #[test]
+    fn test_stable_dynamic_dimension() {
+        let samples = [11.0, 11.5, 12.0, 12.5, 13.0, 13.5, 81.0, 81.5, 82.0, 82.5, 83.0, 83.5]
+            .map(|value| DVector::from_vec(vec![value]));
+
+        let sample_count = samples.len();
+        let cluster_count = 2;
+        let (cluster_weights, cluster_means, cluster_covariances, priors, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood, _) = GaussianMixture::new(sample_count, cluster_count).run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+
+        assert!(cluster_weights    .iter().copied()                            .all(f64::is_finite));
+        assert!(cluster_means      .iter().cloned().flat_map(DVector::into_vec).all(f64::is_finite));
+        assert!(cluster_covariances.iter().cloned().flat_map(DMatrix::into_vec).all(f64::is_finite));
+
+        assert!(log_likelihoods.         iter().copied().all(f64::is_finite));
+        assert!(log_marginal_likelihoods.iter().copied().all(f64::is_finite));
+        assert!(priors                  .iter().copied().all(f64::is_finite));
+        assert!(posteriors              .iter().copied().all(f64::is_finite));
+        assert!(data_log_likelihood.is_finite());
+    }
+
+    #[test]
+    fn test_high_dimension() {
+        const DIMENSION : usize = 12;
+
+        let mut rng = thread_rng();
+        let samples = (0..80)
+            .map(|index| DVector::from_fn(DIMENSION, |_| if index < 40 { 0.0 } else { 10.0 } + rng.gen_range(-1.0..1.0)))
+            .collect::<Vec<_>>();
+
+        let (_, cluster_means, _, _, _, _, posteriors, _, _) = GaussianMixture::new(samples.len(), 2).run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+        assert!(cluster_means.iter().cloned().flat_map(DVector::into_vec).all(f64::is_finite));
+        assert!(posteriors.iter().copied().all(f64::is_finite));
+    }
+
+    #[test]
+    fn test_covariance_types() {
+        let mut rng = thread_rng();
+        let samples = (0..60)
+            .map(|index| Vector::from_array([0.0, 0.0, 0.0].map(|offset : f64| offset + if index < 30 { 0.0 } else { 10.0 } + rng.gen_range(-1.0..1.0))))
+            .collect::<Vec<_>>();
+
+        for covariance_type in [CovarianceType::Full, CovarianceType::Diagonal, CovarianceType::Spherical, CovarianceType::Tied] {
+            let gaussian_mixture = GaussianMixture { covariance_type, ..GaussianMixture::new(samples.len(), 2) };
+            let (_, _, cluster_covariances, _, _, _, posteriors, data_log_likelihood, report) = gaussian_mixture.run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+
+            assert!(posteriors.iter().copied().all(f64::is_finite));
+            assert!(data_log_likelihood.is_finite());
+            assert!(report.converged());
+            for cluster_covariance in &cluster_covariances {
+                for j in 0..3 {
+                    for i in 0..3 {
+                        match covariance_type {
+                            CovarianceType::Full | CovarianceType::Tied => assert_eq!(cluster_covariance[(j, i)], cluster_covariance[(i, j)]),
+                            CovarianceType::Diagonal                   => assert!(j == i || cluster_covariance[(j, i)] == 0.0),
+                            CovarianceType::Spherical                  => assert!(if j == i { cluster_covariance[(j, i)] == cluster_covariance[(0, 0)] } else { cluster_covariance[(j, i)] == 0.0 }),
+                        }
+                    }
+                }
+            }
+
+            if covariance_type == CovarianceType::Tied {
+                assert_eq!(cluster_covariances[0].as_slice(), cluster_covariances[1].as_slice());
+            }
+        }
+    }
+
+    #[test]
+    fn test_regularization() {
+        // All samples of a cluster coincide, which would give a zero covariance matrix without
+        // regularization.
+        let samples = [0.0, 0.0, 0.0, 5.0, 5.0, 5.0].map(|value| Vector::from_array([value, value]));
+        let gaussian_mixture = GaussianMixture { covariance_regularization : 1e-3, ..GaussianMixture::new(samples.len(), 2) };
+        let (_, _, cluster_covariances, _, _, _, posteriors, _, _) = gaussian_mixture.run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+
+        assert!(posteriors.iter().copied().all(f64::is_finite));
+        for cluster_covariance in &cluster_covariances {
+            assert!(Cholesky::new(cluster_covariance).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_outlier() {
+        // The outlier is so far away from both clusters that its likelihoods underflow to zero
+        // outside of log space. It should still be assigned entirely to the nearer cluster.
+        let samples = [0.0, 0.5, 1.0, 1.5, 10.0, 10.5, 11.0, 11.5, 1000.0].map(|value| Vector::from_array([value]));
+
+        let gaussian_mixture = GaussianMixture::new(samples.len(), 2);
+        let cluster_weights = [0.5, 0.5];
+        let cluster_means = [Vector::from_array([0.75]), Vector::from_array([10.75])];
+        let cluster_covariances = [Matrix::one() * 0.3, Matrix::one() * 0.3];
+        let (_, log_likelihoods, log_marginal_likelihoods, posteriors, data_log_likelihood) = gaussian_mixture.e_step(&samples, &cluster_weights, &cluster_means, &cluster_covariances);
+
+        assert!(log_likelihoods.iter().copied().all(f64::is_finite));
+        assert!(log_marginal_likelihoods.iter().copied().all(f64::is_finite));
+        assert!(data_log_likelihood.is_finite());
+
+        assert!((posteriors[samples.len() + 8] - 1.0).abs() < 1e-12);
+        assert!(posteriors[8].abs() < 1e-12);
+        for sample_index in 0..samples.len() {
+            assert!((posteriors[sample_index] + posteriors[samples.len() + sample_index] - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_coincident_samples() {
+        // All initial cluster means coincide, so that only the first cluster is assigned any
+        // sample, and the other clusters have zero total responsibility.
+        let samples = vec![Vector::from_array([0.2, 0.4, 0.6]); 50];
+        for covariance_type in [CovarianceType::Full, CovarianceType::Diagonal, CovarianceType::Spherical, CovarianceType::Tied] {
+            let gaussian_mixture = GaussianMixture { covariance_type, ..GaussianMixture::new(samples.len(), 3) };
+            let (cluster_weights, cluster_means, cluster_covariances, _, _, _, posteriors, data_log_likelihood, ..) = gaussian_mixture.run(&samples, ClusterInit::Llyod, &mut thread_rng());
+            assert!(cluster_weights.iter().copied().all(f64::is_finite));
+            assert!(cluster_means.iter().all(|mean| mean.distance(&samples[0]) < 1e-9));
+            assert!(cluster_covariances.iter().flat_map(|covariance| covariance.as_slice()).copied().all(f64::is_finite));
+            assert!(posteriors.iter().copied().all(f64::is_finite));
+            assert!(data_log_likelihood.is_finite());
+
+            // A single sample, for which Bessel's correction is undefined.
+            let gaussian_mixture = GaussianMixture { covariance_type, ..GaussianMixture::new(1, 1) };
+            let (_, _, cluster_covariances, ..) = gaussian_mixture.run(&samples[..1], ClusterInit::Llyod, &mut thread_rng());
+            assert!(cluster_covariances.iter().flat_map(|covariance| covariance.as_slice()).copied().all(f64::is_finite));
+        }
+
+        // A cluster with zero total responsibility in the maximization step.
+        let samples = [0.0, 1.0, 2.0, 3.0].map(|value| Vector::from_array([value]));
+        let posteriors = [1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+        let (cluster_weights, cluster_means, cluster_covariances) = GaussianMixture::new(samples.len(), 2).m_step(&samples, &[0.5, 0.5], &[0.0; 8], &[0.0; 4], &posteriors);
+        assert_eq!(cluster_weights, vec![1.0, 0.0]);
+        assert_eq!(cluster_means[1][0], 1.5);
+        assert!(cluster_covariances[1][(0, 0)].is_finite());
+    }
+
+    #[test]
+    fn test_stopping_criteria() {
+        let mut rng = thread_rng();
+        let samples = (0..100).map(|_| Vector::from_array([rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)])).collect::<Vec<_>>();
+
+        let stopping_criteria = StoppingCriteria { max_iterations : 3, objective_tolerance : 0.0, parameter_tolerance : 0.0 };
+        let gaussian_mixture = GaussianMixture { stopping_criteria, ..GaussianMixture::new(samples.len(), 4) };
+        let (_, _, _, _, _, _, _, _, report) = gaussian_mixture.run(&samples, ClusterInit::Llyod, &mut rng);
+        assert!(report.iterations <= 3);
+
+        let stopping_criteria = StoppingCriteria { max_iterations : 100, objective_tolerance : f64::INFINITY, parameter_tolerance : 0.0 };
+        let gaussian_mixture = GaussianMixture { stopping_criteria, ..GaussianMixture::new(samples.len(), 4) };
+        let (_, _, _, _, _, _, _, _, report) = gaussian_mixture.run(&samples, ClusterInit::Llyod, &mut rng);
+        assert_eq!(report, ConvergenceReport { iterations : 1, reason : StopReason::ObjectiveTolerance });
+    }
+
+    #[test]
+    fn test_restarts() {
+        let mut rng = thread_rng();
+        let samples = (0..100).map(|_| Vector::from_array([rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)])).collect::<Vec<_>>();
+        let gaussian_mixture = GaussianMixture::new(samples.len(), 3);
+
+        let restarts = Restarts { count : 4, parallel : true };
+        let (_, _, _, _, _, _, posteriors, data_log_likelihood, _, seed) = gaussian_mixture.run_restarts(&samples, ClusterInit::KMeanPlusPlus, restarts, &mut StdRng::seed_from_u64(42));
+
+        // The winning run is reproducible from its seed.
+        let (_, _, _, _, _, _, reproduced_posteriors, reproduced_data_log_likelihood, _) = gaussian_mixture.run(&samples, ClusterInit::KMeanPlusPlus, &mut StdRng::seed_from_u64(seed));
+        assert_eq!(posteriors, reproduced_posteriors);
+        assert_eq!(data_log_likelihood, reproduced_data_log_likelihood);
+
+        // No run from the same seeds beats the winner.
+        let mut seed_rng = StdRng::seed_from_u64(42);
+        for _ in 0..restarts.count {
+            let (_, _, _, _, _, _, _, other_data_log_likelihood, _) = gaussian_mixture.run(&samples, ClusterInit::KMeanPlusPlus, &mut StdRng::seed_from_u64(seed_rng.gen()));
+            assert!(data_log_likelihood >= other_data_log_likelihood);
+        }
+    }
+}
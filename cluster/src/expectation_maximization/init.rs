@@ -2,6 +2,7 @@ use super::slice_random_ext::SliceRandomExt;
 
 use math::prelude::*;
 use rand::prelude::*;
+use rand::distributions::WeightedIndex;
 
 use clap::ValueEnum;
 
@@ -12,12 +13,13 @@ pub enum ClusterInit {
 }
 
 impl ClusterInit {
-    pub(crate) fn init<'a, R, const N: usize>(self, rng : &'a mut R, samples : &'a [Vector<N>], k : usize) -> Vec<Vector<N>>
+    pub(crate) fn init<'a, R, P>(self, rng : &'a mut R, samples : &'a [P], k : usize) -> Vec<P>
     where
-        R: Rng
+        R: Rng,
+        P: Point,
     {
         match self {
-            Self::Llyod => samples.choose_multiple(rng, k).copied().collect(),
+            Self::Llyod => samples.choose_multiple(rng, k).cloned().collect(),
             Self::KMeanPlusPlus => {
                 let mut result = Vec::with_capacity(k);
 
@@ -27,15 +29,15 @@ impl ClusterInit {
                 // 1: Pick initial element and update weights. After that our weights array should
                 //    be initialized and usable.
                 if iter.next().is_some() {
-                    let mean = samples.choose(rng).copied().unwrap();
-                    errors.iter_mut().zip(samples.iter().copied()).for_each(|(error, sample)| *error = error.min((mean - sample).squared_length()));
+                    let mean = samples.choose(rng).cloned().unwrap();
+                    errors.iter_mut().zip(samples.iter()).for_each(|(error, sample)| *error = error.min(mean.squared_distance(sample)));
                     result.push(mean);
                 }
 
                 // 2: Pick other elements and update weights.
                 for _ in iter.by_ref() {
-                    let Ok(mean) = samples.choose_with_weights(rng, &errors).copied() else { break };
-                    errors.iter_mut().zip(samples.iter().copied()).for_each(|(error, sample)| *error = error.min((mean - sample).squared_length()));
+                    let Ok(mean) = samples.choose_with_weights(rng, &errors).cloned() else { break };
+                    errors.iter_mut().zip(samples.iter()).for_each(|(error, sample)| *error = error.min(mean.squared_distance(sample)));
                     result.push(mean);
                 }
 
@@ -46,8 +48,8 @@ impl ClusterInit {
                 //    would be 0/(0+0...+0)). In that case, we fall back to picking elemenet
                 //    normally with replacement.
                 for _ in iter.by_ref() {
-                    let mean = samples.choose(rng).copied().unwrap();
-                    errors.iter_mut().zip(samples.iter().copied()).for_each(|(error, sample)| *error = error.min((mean - sample).squared_length()));
+                    let mean = samples.choose(rng).cloned().unwrap();
+                    errors.iter_mut().zip(samples.iter()).for_each(|(error, sample)| *error = error.min(mean.squared_distance(sample)));
                     result.push(mean);
                 }
 
@@ -55,4 +57,42 @@ impl ClusterInit {
             },
         }
     }
+
+    /// Like [Self::init], but pick k distinct sample indices using dissimilarity between samples
+    /// given by index, for algorithms whose cluster centers must be samples.
+    ///
+    /// For [Self::KMeanPlusPlus], samples are picked with probability proportional to their
+    /// dissimilarity to the nearest picked sample, so that this is exactly k-means++ if
+    /// dissimilarity is squared euclidean distance.
+    pub(crate) fn init_indices<R, D>(self, rng : &mut R, sample_count : usize, k : usize, mut dissimilarity : D) -> Vec<usize>
+    where
+        R: Rng,
+        D: FnMut(usize, usize) -> f64,
+    {
+        assert!(k <= sample_count);
+        match self {
+            Self::Llyod => rand::seq::index::sample(rng, sample_count, k).into_vec(),
+            Self::KMeanPlusPlus => {
+                let mut result = Vec::with_capacity(k);
+                let mut picked = vec![false; sample_count];
+                let mut errors = vec![f64::INFINITY; sample_count];
+                while result.len() < k {
+                    // Fall back to picking uniformly among the remaining samples in the first
+                    // iteration or if the weights are malformed, for example if all remaining
+                    // samples coincide with picked ones.
+                    let weights = (0..sample_count).map(|index| if picked[index] { 0.0 } else { errors[index] });
+                    let distr = if result.is_empty() { None } else { WeightedIndex::new(weights).ok() };
+                    let index = match distr {
+                        Some(distr) => distr.sample(rng),
+                        None => (0..sample_count).filter(|&index| !picked[index]).choose(rng).unwrap(),
+                    };
+
+                    picked[index] = true;
+                    errors.iter_mut().enumerate().for_each(|(other, error)| *error = error.min(dissimilarity(index, other)));
+                    result.push(index);
+                }
+                result
+            },
+        }
+    }
 }
@@ -1,28 +1,84 @@
 use super::init::ClusterInit;
+use super::stopping_criteria::*;
+use super::restarts::Restarts;
+use super::k_means_bounds::Bounds;
 
-use math::prelude::Vector;
+use math::prelude::*;
 use rand::prelude::*;
 
+use clap::ValueEnum;
+
+/// What to do with a cluster that has no sample assigned to it in the maximization step.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum EmptyClusterStrategy {
+    /// Keep the mean of the cluster from the previous iteration.
+    KeepPrevious,
+    /// Move the mean of the cluster to the sample farthest from the mean of its own cluster.
+    #[default]
+    ReseedFarthest,
+    /// Split the largest cluster in two, by moving samples of the largest cluster which are closer
+    /// to its farthest sample than to its mean into the empty cluster.
+    SplitLargest,
+    /// Remove the cluster, so that fewer cluster means are returned.
+    Drop,
+}
+
+/// Algorithm for the expectation step of [KMeans::run].
+///
+/// All algorithms produce identical labels, but accelerated algorithms use the triangle
+/// inequality to skip most distance computations.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum KMeansAlgorithm {
+    /// Brute force search over all cluster means as in [KMeans::e_step].
+    Lloyd,
+    /// Hamerly's algorithm with one lower bound per sample, which is best for few clusters.
+    Hamerly,
+    /// Elkan's algorithm with one lower bound per sample per cluster, which is best for many
+    /// clusters but uses O(sample_count * cluster_count) memory.
+    Elkan,
+    /// Elkan for more than 20 clusters in 16 or more dimensions unless its bounds would take more
+    /// than 1GiB, and Hamerly otherwise.
+    ///
+    /// In low dimensions such as color spaces, a distance computation is so cheap that maintaining
+    /// one lower bound per cluster costs as much as brute force search, so Elkan does not pay off.
+    #[default]
+    Auto,
+}
+
 /// Implementation of K-Means Clustering algorithm.
 #[derive(Debug, Copy, Clone)]
-pub struct KMeans<const N: usize> {
+pub struct KMeans {
     pub sample_count : usize,
     pub cluster_count : usize,
+    pub stopping_criteria : StoppingCriteria,
+    pub empty_cluster_strategy : EmptyClusterStrategy,
+    pub algorithm : KMeansAlgorithm,
 }
 
-impl<const N: usize> KMeans<N> {
+impl KMeans {
     /// Constructor.
+    ///
+    /// By default, the algorithm runs until sample_labels stop changing, for at most
+    /// [StoppingCriteria::default] iterations, empty clusters are reseeded at the farthest sample
+    /// and the expectation step algorithm is chosen automatically.
     pub fn new(sample_count : usize, cluster_count : usize) -> Self {
-        Self { sample_count, cluster_count, }
+        Self {
+            sample_count,
+            cluster_count,
+            stopping_criteria : StoppingCriteria { objective_tolerance : 0.0, parameter_tolerance : 0.0, ..StoppingCriteria::default() },
+            empty_cluster_strategy : EmptyClusterStrategy::default(),
+            algorithm : KMeansAlgorithm::default(),
+        }
     }
 
     /// K-Means initialization step.
     ///
     /// **Inputs**:  (sample_values) <br/>
     /// **Outputs**: (cluster_means)
-    pub fn init<R>(self, sample_values : &[Vector<N>], init : ClusterInit, rng : &mut R) -> (Vec<Vector<N>>, )
+    pub fn init<P, R>(self, sample_values : &[P], init : ClusterInit, rng : &mut R) -> (Vec<P>, )
     where
-        R: Rng
+        P: Point,
+        R: Rng,
     {
         assert_eq!(self.sample_count, sample_values.len());
         (init.init(rng, sample_values, self.cluster_count), )
@@ -30,17 +86,21 @@ impl<const N: usize> KMeans<N> {
 
     /// K-Means expectation step.
     ///
+    /// There may be fewer than [Self::cluster_count] cluster means if clusters were dropped by
+    /// [EmptyClusterStrategy::Drop].
+    ///
     /// **Inputs**:  (sample_values, cluster_means) <br/>
     /// **Outputs**: (sample_labels, sample_errors)
-    pub fn e_step(self, sample_values : &[Vector<N>], cluster_means : &[Vector<N>]) -> (Vec<usize>, Vec<f64>) {
+    pub fn e_step<P: Point>(self, sample_values : &[P], cluster_means : &[P]) -> (Vec<usize>, Vec<f64>) {
         assert_eq!(self.sample_count, sample_values.len());
-        assert_eq!(self.cluster_count, cluster_means.len());
+        assert!(self.cluster_count >= cluster_means.len());
 
-        let mut sample_labels = vec![self.cluster_count; self.sample_count];
-        let mut sample_errors = vec![f64::INFINITY;      self.sample_count];
+        let cluster_count = cluster_means.len();
+        let mut sample_labels = vec![cluster_count;  self.sample_count];
+        let mut sample_errors = vec![f64::INFINITY; self.sample_count];
         for sample_index in 0..self.sample_count {
-            for cluster_index in 0..self.cluster_count {
-                let error = (sample_values[sample_index] - cluster_means[cluster_index]).squared_length();
+            for cluster_index in 0..cluster_count {
+                let error = sample_values[sample_index].squared_distance(&cluster_means[cluster_index]);
                 if sample_errors[sample_index] > error {
                     sample_labels[sample_index] = cluster_index;
                     sample_errors[sample_index] = error;
@@ -52,27 +112,91 @@ impl<const N: usize> KMeans<N> {
 
     /// K-Means maximization step.
     ///
-    /// **Inputs**:  (sample_values, sample_labels, sample_errors) <br/>
+    /// Clusters with no sample are handled according to [Self::empty_cluster_strategy], which
+    /// is why the previous cluster_means are required.
+    ///
+    /// **Inputs**:  (sample_values, sample_labels, sample_errors, cluster_means) <br/>
     /// **Outputs**: (cluster_means)
-    pub fn m_step(self, sample_values : &[Vector<N>], sample_labels : &[usize], sample_errors : &[f64]) -> (Vec<Vector<N>>,) {
+    pub fn m_step<P: Point>(self, sample_values : &[P], sample_labels : &[usize], sample_errors : &[f64], cluster_means : &[P]) -> (Vec<P>,) {
         assert_eq!(self.sample_count, sample_values.len());
         assert_eq!(self.sample_count, sample_labels.len());
         assert_eq!(self.sample_count, sample_errors.len());
+        assert!(self.cluster_count >= cluster_means.len());
+
+        let dimension = sample_values.first().map_or(0, P::dimension);
+        let cluster_count = cluster_means.len();
 
-        let mut cluster_totals = vec![Vector::zero(); self.cluster_count];
-        let mut cluster_counts = vec![0usize;         self.cluster_count];
+        let mut cluster_totals = vec![P::zero(dimension); cluster_count];
+        let mut cluster_counts = vec![0usize;             cluster_count];
         for sample_index in 0..self.sample_count {
-            cluster_totals[sample_labels[sample_index]] += sample_values[sample_index];
+            cluster_totals[sample_labels[sample_index]] += sample_values[sample_index].clone();
             cluster_counts[sample_labels[sample_index]] += 1;
         }
 
-        let mut cluster_means = vec![Vector::zero(); self.cluster_count];
-        for cluster_index in 0..self.cluster_count {
-            cluster_means[cluster_index] = if cluster_counts[cluster_index] != 0 {
-                cluster_totals[cluster_index] / cluster_counts[cluster_index] as f64
-            } else {
-                // TODO: Allow caller to specify what to do in this case.
-                Vector::zero()
+        let mut cluster_means = cluster_means.to_vec();
+        match self.empty_cluster_strategy {
+            EmptyClusterStrategy::KeepPrevious => {
+                for cluster_index in 0..cluster_count {
+                    if cluster_counts[cluster_index] != 0 {
+                        cluster_means[cluster_index] = cluster_totals[cluster_index].clone() / cluster_counts[cluster_index] as f64;
+                    }
+                }
+            }
+            EmptyClusterStrategy::ReseedFarthest => {
+                // Each sample can only be used to reseed one cluster, so that multiple empty
+                // clusters do not end up with the same mean.
+                let mut sample_errors = sample_errors.to_vec();
+                for cluster_index in 0..cluster_count {
+                    if cluster_counts[cluster_index] != 0 {
+                        cluster_means[cluster_index] = cluster_totals[cluster_index].clone() / cluster_counts[cluster_index] as f64;
+                    } else if let Some(sample_index) = (0..self.sample_count).max_by(|&a, &b| f64::total_cmp(&sample_errors[a], &sample_errors[b])) {
+                        cluster_means[cluster_index] = sample_values[sample_index].clone();
+                        sample_errors[sample_index] = f64::NEG_INFINITY;
+                    }
+                }
+            }
+            EmptyClusterStrategy::SplitLargest => {
+                let mut sample_labels = sample_labels.to_vec();
+                for cluster_index in 0..cluster_count {
+                    if cluster_counts[cluster_index] != 0 {
+                        continue;
+                    }
+
+                    let largest_cluster_index = (0..cluster_count).max_by_key(|&index| cluster_counts[index]).unwrap();
+                    if cluster_counts[largest_cluster_index] < 2 {
+                        continue;
+                    }
+
+                    let largest_cluster_mean = cluster_totals[largest_cluster_index].clone() / cluster_counts[largest_cluster_index] as f64;
+                    let farthest_sample_index = (0..self.sample_count)
+                        .filter(|&sample_index| sample_labels[sample_index] == largest_cluster_index)
+                        .max_by(|&a, &b| f64::total_cmp(&sample_values[a].squared_distance(&largest_cluster_mean), &sample_values[b].squared_distance(&largest_cluster_mean)))
+                        .unwrap();
+
+                    let farthest_sample_value = sample_values[farthest_sample_index].clone();
+                    for sample_index in 0..self.sample_count {
+                        let sample_value = &sample_values[sample_index];
+                        if sample_labels[sample_index] == largest_cluster_index && (sample_index == farthest_sample_index || sample_value.squared_distance(&farthest_sample_value) < sample_value.squared_distance(&largest_cluster_mean)) {
+                            sample_labels[sample_index] = cluster_index;
+                            cluster_totals[largest_cluster_index] -= sample_value.clone();
+                            cluster_counts[largest_cluster_index] -= 1;
+                            cluster_totals[cluster_index] += sample_value.clone();
+                            cluster_counts[cluster_index] += 1;
+                        }
+                    }
+                }
+
+                for cluster_index in 0..cluster_count {
+                    if cluster_counts[cluster_index] != 0 {
+                        cluster_means[cluster_index] = cluster_totals[cluster_index].clone() / cluster_counts[cluster_index] as f64;
+                    }
+                }
+            }
+            EmptyClusterStrategy::Drop => {
+                cluster_means = (0..cluster_count)
+                    .filter(|&cluster_index| cluster_counts[cluster_index] != 0)
+                    .map(|cluster_index| cluster_totals[cluster_index].clone() / cluster_counts[cluster_index] as f64)
+                    .collect();
             }
         }
 
@@ -81,35 +205,207 @@ impl<const N: usize> KMeans<N> {
 
     /// K-Means algorithm.
     ///
-    /// Current termination condition is if sample_labels stop changing. An alternative is to
-    /// detect if cluster_means stop changing below some threshold.
+    /// Fewer than [Self::cluster_count] cluster means are returned if clusters were dropped by
+    /// [EmptyClusterStrategy::Drop].
+    ///
+    /// Terminate according to [Self::stopping_criteria], where the objective is inertia and the
+    /// parameters are cluster_means. The algorithm also terminates if sample_labels stop changing,
+    /// since the next update to cluster_means would be zero, which is reported as
+    /// [StopReason::ParameterTolerance].
     ///
     /// **Inputs**:  (sample_values) <br/>
-    /// **Outputs**: (cluster_means, sample_labels, sample_errors)
-    pub fn run<R>(self, sample_values : &[Vector<N>], init : ClusterInit, rng : &mut R) -> (Vec<Vector<N>>, Vec<usize>, Vec<f64>)
+    /// **Outputs**: (cluster_means, sample_labels, sample_errors, report)
+    pub fn run<P, R>(self, sample_values : &[P], init : ClusterInit, rng : &mut R) -> (Vec<P>, Vec<usize>, Vec<f64>, ConvergenceReport)
     where
-        R: Rng
+        P: Point,
+        R: Rng,
     {
-        let (cluster_means,) = self.init(sample_values, init, rng);
+        let (mut cluster_means,) = self.init(sample_values, init, rng);
 
-        let (mut sample_labels, mut sample_errors) = self.e_step(sample_values, &cluster_means);
-        let (mut cluster_means,)                   = self.m_step(sample_values, &sample_labels, &sample_errors);
+        let mut bounds = None;
+        let (mut sample_labels, mut sample_errors) = self.bounded_e_step(sample_values, &cluster_means, &cluster_means, &[], &mut bounds);
+        let mut inertia = sample_errors.iter().sum::<f64>();
+        let mut iterations = 0;
         loop {
-            let (new_sample_labels, new_sample_errors) = self.e_step(sample_values, &cluster_means);
-            let terminate = new_sample_labels.iter().eq(sample_labels.iter());
+            let (new_cluster_means,) = self.m_step(sample_values, &sample_labels, &sample_errors, &cluster_means);
+            let parameter_change = if new_cluster_means.len() == cluster_means.len() {
+                new_cluster_means.iter().cloned().zip(cluster_means.iter().cloned()).map(|(x, y)| x - y).mse()
+            } else {
+                f64::INFINITY
+            };
+            let old_cluster_means = std::mem::replace(&mut cluster_means, new_cluster_means);
+
+            let (new_sample_labels, new_sample_errors) = self.bounded_e_step(sample_values, &old_cluster_means, &cluster_means, &sample_labels, &mut bounds);
+            let new_inertia = new_sample_errors.iter().sum::<f64>();
+            let parameter_change = if new_sample_labels == sample_labels { 0.0 } else { parameter_change };
+            let objective_change = new_inertia - inertia;
             sample_labels = new_sample_labels;
             sample_errors = new_sample_errors;
-            if terminate {
-                break (cluster_means, sample_labels, sample_errors)
-            }
+            inertia = new_inertia;
 
-            let (new_cluster_means,) = self.m_step(sample_values, &sample_labels, &sample_errors);
-            let terminate = false;
-            cluster_means = new_cluster_means;
-            if terminate {
-                break (cluster_means, sample_labels, sample_errors)
+            iterations += 1;
+            if let Some(reason) = self.stopping_criteria.check(iterations, objective_change, parameter_change) {
+                break (cluster_means, sample_labels, sample_errors, ConvergenceReport { iterations, reason })
             }
         }
     }
+
+    /// K-Means expectation step according to [Self::algorithm], keeping bounds across iterations.
+    ///
+    /// Bounds are (re)initialized by brute force search if they are missing or if the number of
+    /// clusters changed.
+    fn bounded_e_step<P: Point>(self, sample_values : &[P], old_cluster_means : &[P], cluster_means : &[P], sample_labels : &[usize], bounds : &mut Option<Bounds>) -> (Vec<usize>, Vec<f64>) {
+        let elkan = match self.algorithm {
+            KMeansAlgorithm::Lloyd   => return self.e_step(sample_values, cluster_means),
+            KMeansAlgorithm::Hamerly => false,
+            KMeansAlgorithm::Elkan   => true,
+            KMeansAlgorithm::Auto    => {
+                let dimension = sample_values.first().map_or(0, P::dimension);
+                cluster_means.len() > 20 && dimension >= 16 && self.sample_count.saturating_mul(cluster_means.len()) <= 1 << 27
+            }
+        };
+
+        let sample_labels = match bounds {
+            Some(bounds) if old_cluster_means.len() == cluster_means.len() => {
+                let mut sample_labels = sample_labels.to_vec();
+                bounds.update(sample_values, old_cluster_means, cluster_means, &mut sample_labels);
+                sample_labels
+            }
+            _ => {
+                let (new_bounds, sample_labels) = Bounds::new(elkan, sample_values, cluster_means);
+                *bounds = Some(new_bounds);
+                sample_labels
+            }
+        };
+
+        let sample_errors = std::iter::zip(sample_values, &sample_labels).map(|(sample_value, &sample_label)| sample_value.squared_distance(&cluster_means[sample_label])).collect();
+        (sample_labels, sample_errors)
+    }
+
+    /// K-Means algorithm with multiple random initializations.
+    ///
+    /// Run the algorithm once per restart and keep the run with the lowest inertia. The seed of
+    /// the winning run is returned, see [Restarts] for how to reproduce it.
+    ///
+    /// **Inputs**:  (sample_values) <br/>
+    /// **Outputs**: (cluster_means, sample_labels, sample_errors, report, seed)
+    pub fn run_restarts<P, R>(self, sample_values : &[P], init : ClusterInit, restarts : Restarts, rng : &mut R) -> (Vec<P>, Vec<usize>, Vec<f64>, ConvergenceReport, u64)
+    where
+        P: Point,
+        R: Rng,
+    {
+        let ((cluster_means, sample_labels, sample_errors, report), seed) = restarts.best_of(rng,
+            |rng| self.run(sample_values, init, rng),
+            |(_, _, sample_errors, _)| sample_errors.iter().sum::<f64>(),
+        );
+        (cluster_means, sample_labels, sample_errors, report, seed)
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dynamic_dimension() {
+        let samples = [
+            DVector::from_vec(vec![0.0, 0.0, 0.0, 0.0, 0.0]),
+            DVector::from_vec(vec![0.1, 0.0, 0.0, 0.1, 0.0]),
+            DVector::from_vec(vec![0.0, 0.1, 0.0, 0.0, 0.1]),
+
+            DVector::from_vec(vec![9.0, 9.0, 9.0, 9.0, 9.0]),
+            DVector::from_vec(vec![9.1, 9.0, 9.0, 9.1, 9.0]),
+            DVector::from_vec(vec![9.0, 9.1, 9.0, 9.0, 9.1]),
+        ];
+
+        let (cluster_means, sample_labels, _, report) = KMeans::new(samples.len(), 2).run(&samples, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+        assert!(report.converged());
+        assert!(cluster_means.iter().all(|cluster_mean| cluster_mean.len() == 5));
+        assert_eq!(sample_labels[0], sample_labels[1]);
+        assert_eq!(sample_labels[0], sample_labels[2]);
+        assert_eq!(sample_labels[3], sample_labels[4]);
+        assert_eq!(sample_labels[3], sample_labels[5]);
+        assert_ne!(sample_labels[0], sample_labels[3]);
+    }
+
+    #[test]
+    fn test_max_iterations() {
+        let mut rng = thread_rng();
+        let samples = (0..200).map(|_| Vector::from_array([rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)])).collect::<Vec<_>>();
+
+        let k_means = KMeans { stopping_criteria : StoppingCriteria { max_iterations : 1, ..KMeans::new(samples.len(), 8).stopping_criteria }, ..KMeans::new(samples.len(), 8) };
+        let (_, _, _, report) = k_means.run(&samples, ClusterInit::Llyod, &mut rng);
+        assert_eq!(report.iterations, 1);
+    }
+
+    #[test]
+    fn test_empty_cluster_strategy() {
+        // Cluster 1 sits far away from every sample so that it is empty after the expectation step.
+        let samples = [-3.0, 2.0, 2.0, 10.0, 11.0, 14.0].map(|value| Vector::from_array([value]));
+        let cluster_means = [Vector::from_array([6.0]), Vector::from_array([100.0])];
+
+        for (empty_cluster_strategy, expected) in [
+            (EmptyClusterStrategy::KeepPrevious,   vec![6.0, 100.0]),
+            (EmptyClusterStrategy::ReseedFarthest, vec![6.0, -3.0]),
+            (EmptyClusterStrategy::SplitLargest,   vec![7.8, -3.0]),
+            (EmptyClusterStrategy::Drop,           vec![6.0]),
+        ] {
+            let k_means = KMeans { empty_cluster_strategy, ..KMeans::new(samples.len(), 2) };
+            let (sample_labels, sample_errors) = k_means.e_step(&samples, &cluster_means);
+            assert!(sample_labels.iter().all(|&sample_label| sample_label == 0));
+
+            let (cluster_means,) = k_means.m_step(&samples, &sample_labels, &sample_errors, &cluster_means);
+            let cluster_means = cluster_means.iter().map(|cluster_mean| cluster_mean[0]).collect::<Vec<_>>();
+            assert_eq!(cluster_means, expected, "empty_cluster_strategy = {empty_cluster_strategy:?}");
+
+            // The full algorithm must be able to continue from there.
+            let (cluster_means, sample_labels, _, report) = k_means.run(&samples, ClusterInit::Llyod, &mut thread_rng());
+            assert!(report.converged());
+            assert!(sample_labels.iter().all(|&sample_label| sample_label < cluster_means.len()));
+        }
+    }
+
+    #[test]
+    fn test_restarts() {
+        let mut rng = thread_rng();
+        let samples = (0..300).map(|_| Vector::from_array([rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)])).collect::<Vec<_>>();
+        let k_means = KMeans::new(samples.len(), 6);
+
+        let restarts = Restarts { count : 8, parallel : true };
+        let (cluster_means, sample_labels, sample_errors, _, seed) = k_means.run_restarts(&samples, ClusterInit::Llyod, restarts, &mut StdRng::seed_from_u64(42));
+        let inertia = sample_errors.iter().sum::<f64>();
+
+        // The winning run is reproducible from its seed.
+        let (reproduced_cluster_means, reproduced_sample_labels, _, _) = k_means.run(&samples, ClusterInit::Llyod, &mut StdRng::seed_from_u64(seed));
+        assert!(std::iter::zip(&cluster_means, &reproduced_cluster_means).all(|(a, b)| a.as_slice() == b.as_slice()));
+        assert_eq!(sample_labels, reproduced_sample_labels);
+
+        // No run from the same seeds beats the winner.
+        let mut seed_rng = StdRng::seed_from_u64(42);
+        for _ in 0..restarts.count {
+            let (_, _, sample_errors, _) = k_means.run(&samples, ClusterInit::Llyod, &mut StdRng::seed_from_u64(seed_rng.gen()));
+            assert!(inertia <= sample_errors.iter().sum::<f64>());
+        }
+    }
+
+    #[test]
+    fn test_algorithm() {
+        let mut rng = thread_rng();
+        for (cluster_count, cluster_init) in [(1, ClusterInit::Llyod), (4, ClusterInit::Llyod), (32, ClusterInit::KMeanPlusPlus)] {
+            // Integer coordinates give plenty of exact ties between distances.
+            let samples = (0..500).map(|_| Vector::from_array([rng.gen_range(0..16) as f64, rng.gen_range(0..16) as f64, rng.gen_range(0..16) as f64])).collect::<Vec<_>>();
+            let seed = rng.gen();
+
+            let k_means = KMeans { algorithm : KMeansAlgorithm::Lloyd, ..KMeans::new(samples.len(), cluster_count) };
+            let (cluster_means, sample_labels, sample_errors, report) = k_means.run(&samples, cluster_init, &mut StdRng::seed_from_u64(seed));
+            for algorithm in [KMeansAlgorithm::Hamerly, KMeansAlgorithm::Elkan, KMeansAlgorithm::Auto] {
+                let k_means = KMeans { algorithm, ..k_means };
+                let (other_cluster_means, other_sample_labels, other_sample_errors, other_report) = k_means.run(&samples, cluster_init, &mut StdRng::seed_from_u64(seed));
+                assert_eq!(sample_labels, other_sample_labels, "algorithm = {algorithm:?}");
+                assert_eq!(sample_errors, other_sample_errors, "algorithm = {algorithm:?}");
+                assert_eq!(report, other_report, "algorithm = {algorithm:?}");
+                assert!(std::iter::zip(&cluster_means, &other_cluster_means).all(|(a, b)| a.as_slice() == b.as_slice()));
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Triangle inequality bounds for accelerated exact K-Means expectation step.
+//!
+//! See Hamerly, "Making k-means even faster" (2010) and Elkan, "Using the triangle inequality to
+//! accelerate k-means" (2003). Both keep an upper bound on the distance from each sample to its
+//! assigned cluster mean and lower bound(s) on the distance to other cluster means, so that most
+//! distance computations can be skipped. Hamerly keeps a single lower bound per sample, which is
+//! cheap to maintain but loose for many clusters. Elkan keeps one lower bound per sample per
+//! cluster, which is tight but costs O(sample_count * cluster_count) memory.
+
+use math::prelude::*;
+
+/// Relative slack for bound comparisons.
+///
+/// A cluster is only pruned if it is farther than the assigned cluster by more than this relative
+/// margin, so that rounding errors in distances and accumulated drifts can never prune a cluster
+/// which is actually at least as close. Otherwise, labels could differ from brute force search in
+/// case of (near) ties.
+const SLACK : f64 = 1e-9;
+
+fn prunable(upper : f64, lower : f64) -> bool {
+    upper * (1.0 + SLACK) < lower
+}
+
+/// Return true if cluster a with squared distance a_error should be preferred to cluster b with
+/// squared distance b_error.
+///
+/// This replicates tie-breaking of brute force search, which is to pick the cluster with the
+/// smallest index among those with minimum squared distance.
+fn preferred(a : usize, a_error : f64, b : usize, b_error : f64) -> bool {
+    a_error < b_error || (a_error == b_error && a < b)
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Bounds {
+    Hamerly {
+        upper : Vec<f64>,
+        lower : Vec<f64>,
+    },
+    Elkan {
+        cluster_count : usize,
+        upper : Vec<f64>,
+        lower : Vec<f64>,
+    },
+}
+
+impl Bounds {
+    /// Assign every sample by brute force search and initialize bounds.
+    pub(crate) fn new<P: Point>(elkan : bool, sample_values : &[P], cluster_means : &[P]) -> (Self, Vec<usize>) {
+        let sample_count = sample_values.len();
+        let cluster_count = cluster_means.len();
+
+        let mut sample_labels = vec![cluster_count; sample_count];
+        let mut upper = vec![f64::INFINITY; sample_count];
+        if elkan {
+            let mut lower = vec![0.0; sample_count * cluster_count];
+            for sample_index in 0..sample_count {
+                let mut best_error = f64::INFINITY;
+                for cluster_index in 0..cluster_count {
+                    let error = sample_values[sample_index].squared_distance(&cluster_means[cluster_index]);
+                    lower[sample_index * cluster_count + cluster_index] = error.sqrt();
+                    if best_error > error {
+                        sample_labels[sample_index] = cluster_index;
+                        best_error = error;
+                    }
+                }
+                upper[sample_index] = best_error.sqrt();
+            }
+            (Bounds::Elkan { cluster_count, upper, lower }, sample_labels)
+        } else {
+            let mut lower = vec![0.0; sample_count];
+            for sample_index in 0..sample_count {
+                let (cluster_index, best_error, second_error) = Self::brute_force(&sample_values[sample_index], cluster_means);
+                sample_labels[sample_index] = cluster_index;
+                upper[sample_index] = best_error.sqrt();
+                lower[sample_index] = second_error.sqrt();
+            }
+            (Bounds::Hamerly { upper, lower }, sample_labels)
+        }
+    }
+
+    /// Return (cluster_index, best_error, second_error) by brute force search.
+    fn brute_force<P: Point>(sample_value : &P, cluster_means : &[P]) -> (usize, f64, f64) {
+        let mut best = (cluster_means.len(), f64::INFINITY);
+        let mut second_error = f64::INFINITY;
+        for cluster_index in 0..cluster_means.len() {
+            let error = sample_value.squared_distance(&cluster_means[cluster_index]);
+            if best.1 > error {
+                second_error = best.1;
+                best = (cluster_index, error);
+            } else if second_error > error {
+                second_error = error;
+            }
+        }
+        (best.0, best.1, second_error)
+    }
+
+    /// Update bounds after cluster means moved from old_cluster_means to cluster_means, and
+    /// reassign samples.
+    ///
+    /// The resulting labels are identical to those of brute force search.
+    pub(crate) fn update<P: Point>(&mut self, sample_values : &[P], old_cluster_means : &[P], cluster_means : &[P], sample_labels : &mut [usize]) {
+        let sample_count = sample_values.len();
+        let cluster_count = cluster_means.len();
+        assert_eq!(old_cluster_means.len(), cluster_count);
+        assert_eq!(sample_labels.len(), sample_count);
+
+        let drifts = std::iter::zip(old_cluster_means, cluster_means).map(|(old, new)| old.distance(new)).collect::<Vec<_>>();
+
+        // Distances between cluster means and half distance to nearest other cluster mean.
+        let mut cluster_distances = vec![0.0; cluster_count * cluster_count];
+        for a in 0..cluster_count {
+            for b in a+1..cluster_count {
+                let distance = cluster_means[a].distance(&cluster_means[b]);
+                cluster_distances[a * cluster_count + b] = distance;
+                cluster_distances[b * cluster_count + a] = distance;
+            }
+        }
+        let separations = (0..cluster_count)
+            .map(|a| 0.5 * (0..cluster_count).filter(|&b| b != a).map(|b| cluster_distances[a * cluster_count + b]).fold(f64::INFINITY, f64::min))
+            .collect::<Vec<_>>();
+
+        match self {
+            Bounds::Hamerly { upper, lower } => {
+                let mut largest_drift = (cluster_count, 0.0);
+                let mut second_largest_drift = 0.0;
+                for cluster_index in 0..cluster_count {
+                    if drifts[cluster_index] > largest_drift.1 {
+                        second_largest_drift = largest_drift.1;
+                        largest_drift = (cluster_index, drifts[cluster_index]);
+                    } else if drifts[cluster_index] > second_largest_drift {
+                        second_largest_drift = drifts[cluster_index];
+                    }
+                }
+
+                for sample_index in 0..sample_count {
+                    let label = sample_labels[sample_index];
+                    upper[sample_index] += drifts[label];
+                    lower[sample_index] -= if label == largest_drift.0 { second_largest_drift } else { largest_drift.1 };
+
+                    let bound = separations[label].max(lower[sample_index]);
+                    if prunable(upper[sample_index], bound) {
+                        continue;
+                    }
+
+                    upper[sample_index] = sample_values[sample_index].distance(&cluster_means[label]);
+                    if prunable(upper[sample_index], bound) {
+                        continue;
+                    }
+
+                    let (cluster_index, best_error, second_error) = Self::brute_force(&sample_values[sample_index], cluster_means);
+                    sample_labels[sample_index] = cluster_index;
+                    upper[sample_index] = best_error.sqrt();
+                    lower[sample_index] = second_error.sqrt();
+                }
+            }
+            Bounds::Elkan { cluster_count : bounds_cluster_count, upper, lower } => {
+                assert_eq!(*bounds_cluster_count, cluster_count);
+
+                for sample_index in 0..sample_count {
+                    let label = sample_labels[sample_index];
+                    upper[sample_index] += drifts[label];
+                    for cluster_index in 0..cluster_count {
+                        let bound = &mut lower[sample_index * cluster_count + cluster_index];
+                        *bound = (*bound - drifts[cluster_index]).max(0.0);
+                    }
+
+                    if prunable(upper[sample_index], separations[label]) {
+                        continue;
+                    }
+
+                    let mut label = label;
+                    let mut best_error = None;
+                    for cluster_index in 0..cluster_count {
+                        if cluster_index == label
+                            || prunable(upper[sample_index], lower[sample_index * cluster_count + cluster_index])
+                            || prunable(upper[sample_index], 0.5 * cluster_distances[label * cluster_count + cluster_index]) {
+                            continue;
+                        }
+
+                        let current_best_error = match best_error {
+                            Some(best_error) => best_error,
+                            None => {
+                                let error = sample_values[sample_index].squared_distance(&cluster_means[label]);
+                                upper[sample_index] = error.sqrt();
+                                lower[sample_index * cluster_count + label] = upper[sample_index];
+                                best_error = Some(error);
+                                if prunable(upper[sample_index], lower[sample_index * cluster_count + cluster_index])
+                                    || prunable(upper[sample_index], 0.5 * cluster_distances[label * cluster_count + cluster_index]) {
+                                    continue;
+                                }
+                                error
+                            }
+                        };
+
+                        let error = sample_values[sample_index].squared_distance(&cluster_means[cluster_index]);
+                        lower[sample_index * cluster_count + cluster_index] = error.sqrt();
+                        if preferred(cluster_index, error, label, current_best_error) {
+                            label = cluster_index;
+                            best_error = Some(error);
+                            upper[sample_index] = error.sqrt();
+                        }
+                    }
+                    sample_labels[sample_index] = label;
+                }
+            }
+        }
+    }
+}
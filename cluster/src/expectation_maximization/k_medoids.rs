@@ -0,0 +1,280 @@
+use super::init::ClusterInit;
+use super::stopping_criteria::*;
+
+use crate::distance_matrix::DistanceMatrix;
+use crate::metric::Metric;
+
+use rand::prelude::*;
+
+/// Nearest and second nearest medoid of a sample.
+#[derive(Debug, Clone, Copy)]
+struct Assignment {
+    nearest : usize,
+    nearest_dissimilarity : f64,
+    second : usize,
+    second_dissimilarity : f64,
+}
+
+impl Assignment {
+    fn new<D>(medoids : &[usize], sample_index : usize, dissimilarity : &D) -> Self
+    where
+        D: Fn(usize, usize) -> f64
+    {
+        let mut assignment = Self { nearest : 0, nearest_dissimilarity : f64::INFINITY, second : 0, second_dissimilarity : f64::INFINITY };
+        for (cluster_index, &medoid) in medoids.iter().enumerate() {
+            assignment.insert(cluster_index, dissimilarity(sample_index, medoid));
+        }
+        assignment
+    }
+
+    fn insert(&mut self, cluster_index : usize, dissimilarity : f64) {
+        if dissimilarity < self.nearest_dissimilarity {
+            self.second = self.nearest;
+            self.second_dissimilarity = self.nearest_dissimilarity;
+            self.nearest = cluster_index;
+            self.nearest_dissimilarity = dissimilarity;
+        } else if dissimilarity < self.second_dissimilarity {
+            self.second = cluster_index;
+            self.second_dissimilarity = dissimilarity;
+        }
+    }
+}
+
+/// Implementation of K-Medoids clustering with the FasterPAM algorithm.
+///
+/// Unlike [super::k_means::KMeans], cluster centers are samples, called medoids, and samples can be
+/// of any type with any dissimilarity, which need not be a metric. The objective is total
+/// deviation, which is the sum of dissimilarities between samples and their nearest medoid.
+///
+/// See Schubert and Rousseeuw, "Fast and eager k-medoids clustering: O(k) runtime improvement of
+/// the PAM, CLARA, and CLARANS algorithms", 2021.
+#[derive(Debug, Copy, Clone)]
+pub struct KMedoids {
+    pub sample_count : usize,
+    pub cluster_count : usize,
+    pub stopping_criteria : StoppingCriteria,
+}
+
+impl KMedoids {
+    /// Constructor.
+    ///
+    /// By default, the algorithm runs until no swap improves total deviation, for at most
+    /// [StoppingCriteria::default] iterations.
+    pub fn new(sample_count : usize, cluster_count : usize) -> Self {
+        Self {
+            sample_count,
+            cluster_count,
+            stopping_criteria : StoppingCriteria { objective_tolerance : 0.0, parameter_tolerance : 0.0, ..StoppingCriteria::default() },
+        }
+    }
+
+    /// K-Medoids algorithm on samples with dissimilarity between them.
+    ///
+    /// Each iteration is one pass over all samples as candidates for swapping with a medoid, where
+    /// a swap is performed as soon as it improves total deviation. Parameter change is the number
+    /// of swaps in an iteration, so that the algorithm terminates once a pass finds no improving
+    /// swap, reported as [StopReason::ParameterTolerance]. Dissimilarity is evaluated O(n^2) times
+    /// per iteration, so that it may pay off to precompute it, see [Self::run_precomputed].
+    ///
+    /// **Inputs**:  (sample_values) <br/>
+    /// **Outputs**: (cluster_medoids, sample_labels, total_deviation, report)
+    pub fn run<T, D, R>(self, sample_values : &[T], dissimilarity : D, init : ClusterInit, rng : &mut R) -> (Vec<usize>, Vec<usize>, f64, ConvergenceReport)
+    where
+        D: Fn(&T, &T) -> f64,
+        R: Rng,
+    {
+        assert_eq!(self.sample_count, sample_values.len());
+        self.run_indices(|i, j| dissimilarity(&sample_values[i], &sample_values[j]), init, rng)
+    }
+
+    /// K-Medoids algorithm with dissimilarity given by metric.
+    ///
+    /// **Inputs**:  (sample_values) <br/>
+    /// **Outputs**: (cluster_medoids, sample_labels, total_deviation, report)
+    pub fn run_with_metric<P, M, R>(self, sample_values : &[P], metric : &M, init : ClusterInit, rng : &mut R) -> (Vec<usize>, Vec<usize>, f64, ConvergenceReport)
+    where
+        M: Metric<P>,
+        R: Rng,
+    {
+        self.run(sample_values, |a, b| metric.distance(a, b), init, rng)
+    }
+
+    /// K-Medoids algorithm on precomputed dissimilarities.
+    ///
+    /// **Inputs**:  (distance_matrix) <br/>
+    /// **Outputs**: (cluster_medoids, sample_labels, total_deviation, report)
+    pub fn run_precomputed<R : Rng>(self, distance_matrix : &DistanceMatrix, init : ClusterInit, rng : &mut R) -> (Vec<usize>, Vec<usize>, f64, ConvergenceReport) {
+        assert_eq!(self.sample_count, distance_matrix.sample_count());
+        self.run_indices(distance_matrix.dissimilarity(), init, rng)
+    }
+
+    fn run_indices<D, R>(self, dissimilarity : D, init : ClusterInit, rng : &mut R) -> (Vec<usize>, Vec<usize>, f64, ConvergenceReport)
+    where
+        D: Fn(usize, usize) -> f64,
+        R: Rng,
+    {
+        assert!(self.cluster_count > 0 || self.sample_count == 0);
+        assert!(self.cluster_count <= self.sample_count);
+
+        let mut medoids = init.init_indices(rng, self.sample_count, self.cluster_count, &dissimilarity);
+        let mut assignments = (0..self.sample_count).map(|sample_index| Assignment::new(&medoids, sample_index, &dissimilarity)).collect::<Vec<_>>();
+        let mut deviation = assignments.iter().map(|assignment| assignment.nearest_dissimilarity).sum::<f64>();
+
+        // Change in total deviation if a medoid is removed and its samples are reassigned to their
+        // second nearest medoid.
+        let compute_removal_losses = |assignments : &[Assignment]| {
+            let mut removal_losses = vec![0.0; self.cluster_count];
+            for assignment in assignments {
+                removal_losses[assignment.nearest] += assignment.second_dissimilarity - assignment.nearest_dissimilarity;
+            }
+            removal_losses
+        };
+        let mut removal_losses = compute_removal_losses(&assignments);
+
+        let mut candidate_dissimilarities = vec![0.0; self.sample_count];
+        let mut iterations = 0;
+        loop {
+            let mut swaps = 0;
+            for candidate in 0..self.sample_count {
+                if medoids.contains(&candidate) {
+                    continue;
+                }
+
+                // Change in total deviation for swapping each medoid with candidate.
+                let mut changes = removal_losses.clone();
+                let mut shared_change = 0.0;
+                if self.cluster_count == 1 {
+                    // There is no second nearest medoid to fall back to, and every sample moves to
+                    // candidate.
+                    changes[0] = 0.0;
+                }
+                for (sample_index, assignment) in assignments.iter().enumerate() {
+                    let candidate_dissimilarity = dissimilarity(sample_index, candidate);
+                    candidate_dissimilarities[sample_index] = candidate_dissimilarity;
+                    if self.cluster_count == 1 {
+                        shared_change += candidate_dissimilarity - assignment.nearest_dissimilarity;
+                    } else if candidate_dissimilarity < assignment.nearest_dissimilarity {
+                        // Sample moves to candidate regardless of which medoid is removed, rather
+                        // than to its second nearest medoid as accounted for in removal loss.
+                        shared_change += candidate_dissimilarity - assignment.nearest_dissimilarity;
+                        changes[assignment.nearest] += assignment.nearest_dissimilarity - assignment.second_dissimilarity;
+                    } else if candidate_dissimilarity < assignment.second_dissimilarity {
+                        // Sample moves to candidate instead of its second nearest medoid if its
+                        // nearest medoid is removed.
+                        changes[assignment.nearest] += candidate_dissimilarity - assignment.second_dissimilarity;
+                    }
+                }
+
+                let (cluster_index, change) = changes.iter()
+                    .map(|change| change + shared_change)
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+                    .unwrap();
+
+                // Require a strict improvement beyond rounding errors, so that swaps between
+                // equivalent configurations cannot cycle.
+                if change < -f64::EPSILON * deviation.abs() {
+                    medoids[cluster_index] = candidate;
+                    for (sample_index, assignment) in assignments.iter_mut().enumerate() {
+                        if assignment.nearest == cluster_index || assignment.second == cluster_index {
+                            *assignment = Assignment::new(&medoids, sample_index, &dissimilarity);
+                        } else {
+                            assignment.insert(cluster_index, candidate_dissimilarities[sample_index]);
+                        }
+                    }
+                    removal_losses = compute_removal_losses(&assignments);
+                    swaps += 1;
+                }
+            }
+
+            let new_deviation = assignments.iter().map(|assignment| assignment.nearest_dissimilarity).sum::<f64>();
+            let objective_change = new_deviation - deviation;
+            deviation = new_deviation;
+
+            iterations += 1;
+            if let Some(reason) = self.stopping_criteria.check(iterations, objective_change, swaps as f64) {
+                let sample_labels = assignments.iter().map(|assignment| assignment.nearest).collect();
+                break (medoids, sample_labels, deviation, ConvergenceReport { iterations, reason })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric::*;
+    use crate::test_util::levenshtein;
+
+    use math::prelude::*;
+
+    fn total_deviation(dissimilarity : impl Fn(usize, usize) -> f64, sample_count : usize, medoids : &[usize]) -> f64 {
+        (0..sample_count).map(|sample_index| medoids.iter().map(|&medoid| dissimilarity(sample_index, medoid)).fold(f64::INFINITY, f64::min)).sum()
+    }
+
+    #[test]
+    fn test_local_optimum() {
+        let mut rng = thread_rng();
+        for cluster_count in [1, 2, 5] {
+            let samples = (0..60).map(|_| Vector::from_array([rng.gen_range(0.0..10.0), rng.gen_range(0.0..10.0)])).collect::<Vec<_>>();
+            let distance_matrix = DistanceMatrix::from_metric(&samples, &Manhattan);
+            for init in [ClusterInit::Llyod, ClusterInit::KMeanPlusPlus] {
+                let (medoids, sample_labels, deviation, report) = KMedoids::new(samples.len(), cluster_count).run_precomputed(&distance_matrix, init, &mut rng);
+                assert!(report.converged());
+                assert_eq!(medoids.len(), cluster_count);
+
+                // Labels and deviation are consistent with medoids.
+                for (sample_index, &label) in sample_labels.iter().enumerate() {
+                    assert!(medoids.iter().all(|&medoid| distance_matrix.get(sample_index, medoids[label]) <= distance_matrix.get(sample_index, medoid)));
+                }
+                let expected = total_deviation(distance_matrix.dissimilarity(), samples.len(), &medoids);
+                assert!((deviation - expected).abs() < 1e-9);
+
+                // No single swap improves deviation.
+                for cluster_index in 0..cluster_count {
+                    for candidate in 0..samples.len() {
+                        let mut swapped = medoids.clone();
+                        swapped[cluster_index] = candidate;
+                        assert!(total_deviation(distance_matrix.dissimilarity(), samples.len(), &swapped) >= deviation - 1e-9);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dissimilarity() {
+        // Medoids are samples, so that they can be strings.
+        let samples = ["apple", "apply", "ample", "zebra", "zebras", "cobra"];
+        let (medoids, sample_labels, deviation, report) = KMedoids::new(samples.len(), 2).run(&samples, |a, b| levenshtein(a, b), ClusterInit::KMeanPlusPlus, &mut thread_rng());
+        assert!(report.converged());
+        assert_eq!(sample_labels[0], sample_labels[1]);
+        assert_eq!(sample_labels[0], sample_labels[2]);
+        assert_eq!(sample_labels[3], sample_labels[4]);
+        assert_eq!(sample_labels[3], sample_labels[5]);
+        assert_eq!(samples[medoids[sample_labels[0]]], "apple");
+        assert_eq!(samples[medoids[sample_labels[3]]], "zebra");
+        assert_eq!(deviation, 5.0);
+
+        let samples = [Vector::from_array([0.0, 0.0]), Vector::from_array([1.0, 1.0]), Vector::from_array([4.0, 0.0])];
+        let (medoids, _, deviation, _) = KMedoids::new(samples.len(), 1).run_with_metric(&samples, &Chebyshev, ClusterInit::Llyod, &mut thread_rng());
+        assert_eq!(medoids, vec![1]);
+        assert_eq!(deviation, 4.0);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let samples = [Vector::from_array([1.0, 1.0]); 4];
+        let (medoids, sample_labels, deviation, report) = KMedoids::new(samples.len(), 4).run_with_metric(&samples, &Euclidean, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+        assert!(report.converged());
+        medoids.iter().for_each(|&medoid| assert!(medoid < 4));
+        assert_eq!(medoids.iter().copied().collect::<std::collections::HashSet<_>>().len(), 4);
+        assert_eq!(sample_labels.len(), 4);
+        assert_eq!(deviation, 0.0);
+
+        let samples : [Vector<2>; 0] = [];
+        let (medoids, sample_labels, deviation, _) = KMedoids::new(0, 0).run_with_metric(&samples, &Euclidean, ClusterInit::KMeanPlusPlus, &mut thread_rng());
+        assert!(medoids.is_empty() && sample_labels.is_empty());
+        assert_eq!(deviation, 0.0);
+    }
+}
@@ -0,0 +1,209 @@
+use super::init::ClusterInit;
+use super::k_means::KMeans;
+use super::stopping_criteria::*;
+
+use math::prelude::*;
+use rand::prelude::*;
+
+/// Implementation of Mini-Batch K-Means Clustering algorithm.
+///
+/// Instead of touching every sample in every iteration, each iteration draws a small random batch
+/// of samples and moves each cluster mean towards the samples in the batch assigned to it. Each
+/// cluster has its own learning rate, which is the reciprocal of the number of samples assigned to
+/// it so far, so that a cluster mean is the running average of every sample it has ever seen.
+#[derive(Debug, Copy, Clone)]
+pub struct MiniBatchKMeans {
+    pub sample_count : usize,
+    pub cluster_count : usize,
+    /// Number of samples drawn (with replacement) in each iteration.
+    pub batch_size : usize,
+    /// Lower bound on the learning rate of each cluster, in [0, 1].
+    ///
+    /// The default of zero gives the running average. A positive value lets cluster means keep
+    /// adapting in later iterations instead of freezing, at the cost of more noise.
+    pub min_learning_rate : f64,
+    pub stopping_criteria : StoppingCriteria,
+}
+
+impl MiniBatchKMeans {
+    /// Constructor.
+    ///
+    /// By default, 1024 samples are drawn in each iteration for at most 100 iterations, and the
+    /// algorithm stops early once cluster means move less than 1e-4 in mean squared error.
+    pub fn new(sample_count : usize, cluster_count : usize) -> Self {
+        Self {
+            sample_count,
+            cluster_count,
+            batch_size : 1024,
+            min_learning_rate : 0.0,
+            stopping_criteria : StoppingCriteria { max_iterations : 100, ..StoppingCriteria::default() },
+        }
+    }
+
+    /// Mini-Batch K-Means initialization step.
+    ///
+    /// The initialization method is only applied to a random subset of 3 * batch_size samples,
+    /// since methods such as [ClusterInit::KMeanPlusPlus] would otherwise touch every sample.
+    ///
+    /// **Inputs**:  (sample_values) <br/>
+    /// **Outputs**: (cluster_means, cluster_counts)
+    pub fn init<P, R>(self, sample_values : &[P], init : ClusterInit, rng : &mut R) -> (Vec<P>, Vec<usize>)
+    where
+        P: Point,
+        R: Rng,
+    {
+        assert_eq!(self.sample_count, sample_values.len());
+
+        let init_size = (3 * self.batch_size).max(self.cluster_count);
+        let cluster_means = if init_size < self.sample_count {
+            let subset = sample_values.choose_multiple(rng, init_size).cloned().collect::<Vec<_>>();
+            init.init(rng, &subset, self.cluster_count)
+        } else {
+            init.init(rng, sample_values, self.cluster_count)
+        };
+        let cluster_counts = vec![0; cluster_means.len()];
+        (cluster_means, cluster_counts)
+    }
+
+    /// Mini-Batch K-Means step.
+    ///
+    /// Draw a batch, assign each sample in the batch to its nearest cluster mean and then move
+    /// each cluster mean towards the samples assigned to it.
+    ///
+    /// Panic if there are no samples or no cluster means, if [Self::batch_size] is zero or if
+    /// [Self::min_learning_rate] is not in [0, 1].
+    ///
+    /// **Inputs**:  (sample_values, cluster_means, cluster_counts) <br/>
+    /// **Outputs**: (cluster_means, cluster_counts, batch_inertia)
+    pub fn step<P, R>(self, sample_values : &[P], cluster_means : &[P], cluster_counts : &[usize], rng : &mut R) -> (Vec<P>, Vec<usize>, f64)
+    where
+        P: Point,
+        R: Rng,
+    {
+        assert_eq!(self.sample_count, sample_values.len());
+        assert_eq!(cluster_means.len(), cluster_counts.len());
+        assert!(self.sample_count > 0, "cannot draw batch without samples");
+        assert!(!cluster_means.is_empty(), "cannot assign samples without cluster means");
+        assert!(self.batch_size > 0, "batch size must be positive");
+        assert!((0.0..=1.0).contains(&self.min_learning_rate), "min learning rate must be in [0, 1]");
+
+        let batch = (0..self.batch_size).map(|_| rng.gen_range(0..self.sample_count)).collect::<Vec<_>>();
+
+        // 1: Assign samples in batch with respect to cluster means at the start of the step.
+        let mut batch_labels = Vec::with_capacity(batch.len());
+        let mut batch_inertia = 0.0;
+        for &sample_index in &batch {
+            let (cluster_index, error) = cluster_means
+                .iter()
+                .map(|cluster_mean| sample_values[sample_index].squared_distance(cluster_mean))
+                .enumerate()
+                .min_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+                .unwrap();
+            batch_labels.push(cluster_index);
+            batch_inertia += error;
+        }
+
+        // 2: Gradient step with per-cluster learning rate: mean(C) += rate(C) * (X - mean(C))
+        let mut cluster_means = cluster_means.to_vec();
+        let mut cluster_counts = cluster_counts.to_vec();
+        for (&sample_index, &cluster_index) in std::iter::zip(&batch, &batch_labels) {
+            cluster_counts[cluster_index] += 1;
+            let learning_rate = (1.0 / cluster_counts[cluster_index] as f64).max(self.min_learning_rate);
+            let displacement = sample_values[sample_index].clone() - cluster_means[cluster_index].clone();
+            cluster_means[cluster_index] += displacement * learning_rate;
+        }
+
+        (cluster_means, cluster_counts, batch_inertia / batch.len() as f64)
+    }
+
+    /// Mini-Batch K-Means algorithm.
+    ///
+    /// Terminate according to [Self::stopping_criteria], where the objective is the mean squared
+    /// error of batches, smoothed by an exponentially weighted average since a single batch is
+    /// noisy, and the parameters are cluster_means. Every sample is assigned to its nearest
+    /// cluster mean once at the end.
+    ///
+    /// **Inputs**:  (sample_values) <br/>
+    /// **Outputs**: (cluster_means, sample_labels, sample_errors, report)
+    pub fn run<P, R>(self, sample_values : &[P], init : ClusterInit, rng : &mut R) -> (Vec<P>, Vec<usize>, Vec<f64>, ConvergenceReport)
+    where
+        P: Point,
+        R: Rng,
+    {
+        const SMOOTHING : f64 = 0.1;
+
+        let (mut cluster_means, mut cluster_counts) = self.init(sample_values, init, rng);
+        let mut smoothed_inertia = None;
+        let mut iterations = 0;
+        let report = loop {
+            let (new_cluster_means, new_cluster_counts, batch_inertia) = self.step(sample_values, &cluster_means, &cluster_counts, rng);
+            let parameter_change = new_cluster_means.iter().cloned().zip(cluster_means.iter().cloned()).map(|(x, y)| x - y).mse();
+            cluster_means = new_cluster_means;
+            cluster_counts = new_cluster_counts;
+
+            let objective_change = match smoothed_inertia {
+                Some(old_smoothed_inertia) => {
+                    let new_smoothed_inertia = old_smoothed_inertia + SMOOTHING * (batch_inertia - old_smoothed_inertia);
+                    smoothed_inertia = Some(new_smoothed_inertia);
+                    new_smoothed_inertia - old_smoothed_inertia
+                }
+                None => {
+                    smoothed_inertia = Some(batch_inertia);
+                    f64::INFINITY
+                }
+            };
+
+            iterations += 1;
+            if let Some(reason) = self.stopping_criteria.check(iterations, objective_change, parameter_change) {
+                break ConvergenceReport { iterations, reason };
+            }
+        };
+
+        let (sample_labels, sample_errors) = KMeans::new(self.sample_count, cluster_means.len()).e_step(sample_values, &cluster_means);
+        (cluster_means, sample_labels, sample_errors, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separated() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples = (0..5000)
+            .map(|index| {
+                let offset = (index % 4) as f64 * 10.0;
+                Vector::from_array([offset + rng.gen_range(-1.0..1.0), offset + rng.gen_range(-1.0..1.0)])
+            })
+            .collect::<Vec<_>>();
+
+        let mini_batch_k_means = MiniBatchKMeans { batch_size : 100, ..MiniBatchKMeans::new(samples.len(), 4) };
+        let (cluster_means, sample_labels, sample_errors, _) = mini_batch_k_means.run(&samples, ClusterInit::KMeanPlusPlus, &mut rng);
+        assert_eq!(cluster_means.len(), 4);
+
+        // Comparable quality to full K-Means.
+        let (_, _, k_means_sample_errors, _) = KMeans::new(samples.len(), 4).run(&samples, ClusterInit::KMeanPlusPlus, &mut rng);
+        let inertia = sample_errors.iter().sum::<f64>();
+        let k_means_inertia = k_means_sample_errors.iter().sum::<f64>();
+        assert!(inertia <= k_means_inertia * 1.1, "inertia = {inertia}, k_means_inertia = {k_means_inertia}");
+
+        for index in 4..samples.len() {
+            assert_eq!(sample_labels[index], sample_labels[index % 4]);
+        }
+    }
+
+    #[test]
+    fn test_dynamic_dimension() {
+        let samples = (0..200)
+            .map(|index| DVector::from_fn(6, |_| if index < 100 { 0.0 } else { 5.0 } + thread_rng().gen_range(-0.1..0.1)))
+            .collect::<Vec<_>>();
+
+        let mini_batch_k_means = MiniBatchKMeans { batch_size : 16, min_learning_rate : 0.01, ..MiniBatchKMeans::new(samples.len(), 2) };
+        let (cluster_means, sample_labels, _, _) = mini_batch_k_means.run(&samples, ClusterInit::KMeanPlusPlus, &mut StdRng::seed_from_u64(0));
+        assert!(cluster_means.iter().all(|cluster_mean| cluster_mean.len() == 6));
+        assert_ne!(sample_labels[0], sample_labels[199]);
+        assert!(sample_labels[..100].iter().all(|&label| label == sample_labels[0]));
+        assert!(sample_labels[100..].iter().all(|&label| label == sample_labels[199]));
+    }
+}
@@ -1,5 +1,10 @@
 pub mod init;
 pub mod k_means;
+pub mod k_medoids;
+pub mod mini_batch_k_means;
 pub mod gaussian_mixture;
+pub mod stopping_criteria;
+pub mod restarts;
 
 pub(crate) mod slice_random_ext;
+pub(crate) mod k_means_bounds;
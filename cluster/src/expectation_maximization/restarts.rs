@@ -0,0 +1,72 @@
+use rand::prelude::*;
+use rayon::prelude::*;
+
+/// Configuration for running an algorithm from multiple independent random initializations.
+///
+/// A seed is drawn from the caller-supplied rng for each run, and each run uses its own [StdRng]
+/// seeded from it. Hence, the result does not depend on whether runs are executed in parallel,
+/// and the winning run can be reproduced by running once with `StdRng::seed_from_u64(seed)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Restarts {
+    /// Number of runs. Must be non-zero.
+    pub count : usize,
+    /// Execute runs in parallel on the global thread pool.
+    pub parallel : bool,
+}
+
+impl Default for Restarts {
+    fn default() -> Self {
+        Self { count : 1, parallel : false, }
+    }
+}
+
+impl Restarts {
+    /// Execute runs and return the result with the lowest cost, together with its seed.
+    ///
+    /// Ties are resolved in favor of the earliest run, and a NaN cost never wins unless all costs
+    /// are NaN.
+    pub(crate) fn best_of<R, T, F, C>(self, rng : &mut R, run : F, cost : C) -> (T, u64)
+    where
+        R: Rng,
+        T: Send,
+        F: Fn(&mut StdRng) -> T + Sync,
+        C: Fn(&T) -> f64,
+    {
+        assert_ne!(self.count, 0);
+
+        let seeds = (0..self.count).map(|_| rng.gen()).collect::<Vec<u64>>();
+        let results = if self.parallel {
+            seeds.par_iter().map(|&seed| run(&mut StdRng::seed_from_u64(seed))).collect::<Vec<_>>()
+        } else {
+            seeds.iter().map(|&seed| run(&mut StdRng::seed_from_u64(seed))).collect::<Vec<_>>()
+        };
+
+        std::iter::zip(results, seeds)
+            .map(|(result, seed)| {
+                let cost = cost(&result);
+                (if cost.is_nan() { f64::INFINITY } else { cost }, result, seed)
+            })
+            .reduce(|best, current| if current.0 < best.0 { current } else { best })
+            .map(|(_, result, seed)| (result, seed))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_of() {
+        let run = |rng : &mut StdRng| rng.gen_range(0.0..1.0);
+        for parallel in [false, true] {
+            let restarts = Restarts { count : 16, parallel };
+            let (best, seed) = restarts.best_of(&mut StdRng::seed_from_u64(0), run, |&value| value);
+            assert_eq!(best, run(&mut StdRng::seed_from_u64(seed)));
+
+            // Same seeds are drawn regardless of parallelism, so the winner is the same as well.
+            let (other, _) = Restarts { count : 16, parallel : !parallel }.best_of(&mut StdRng::seed_from_u64(0), run, |&value| value);
+            assert_eq!(best, other);
+        }
+    }
+}
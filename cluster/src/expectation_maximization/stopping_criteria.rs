@@ -0,0 +1,84 @@
+/// Stopping criteria shared by iterative expectation-maximization algorithms.
+///
+/// An iteration consists of one maximization step followed by one expectation step. The
+/// algorithm stops as soon as any of the criteria is met.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StoppingCriteria {
+    /// Maximum number of iterations.
+    pub max_iterations : usize,
+    /// Stop if the absolute change in objective between two iterations is at most this value.
+    ///
+    /// The objective is inertia (sum of squared errors) for K-Means and data log likelihood for
+    /// Gaussian mixture model.
+    pub objective_tolerance : f64,
+    /// Stop if the mean squared error of parameter updates in an iteration is at most this value.
+    pub parameter_tolerance : f64,
+}
+
+impl Default for StoppingCriteria {
+    fn default() -> Self {
+        Self { max_iterations : 300, objective_tolerance : 0.0, parameter_tolerance : 1e-4, }
+    }
+}
+
+/// Reason an iterative algorithm stopped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// Change in objective dropped to or below [StoppingCriteria::objective_tolerance].
+    ObjectiveTolerance,
+    /// Parameter updates dropped to or below [StoppingCriteria::parameter_tolerance].
+    ParameterTolerance,
+    /// [StoppingCriteria::max_iterations] was reached without converging.
+    MaxIterations,
+}
+
+/// Report on how an iterative algorithm stopped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConvergenceReport {
+    pub iterations : usize,
+    pub reason : StopReason,
+}
+
+impl ConvergenceReport {
+    /// Return true unless the algorithm was cut short by [StoppingCriteria::max_iterations].
+    pub fn converged(&self) -> bool {
+        self.reason != StopReason::MaxIterations
+    }
+}
+
+impl StoppingCriteria {
+    /// Check criteria after an iteration.
+    ///
+    /// Return the reason to stop if any, with a precedence of parameter tolerance, objective
+    /// tolerance and maximum iterations in that order.
+    pub fn check(&self, iterations : usize, objective_change : f64, parameter_change : f64) -> Option<StopReason> {
+        if parameter_change <= self.parameter_tolerance {
+            Some(StopReason::ParameterTolerance)
+        } else if objective_change.abs() <= self.objective_tolerance {
+            Some(StopReason::ObjectiveTolerance)
+        } else if iterations >= self.max_iterations {
+            Some(StopReason::MaxIterations)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let stopping_criteria = StoppingCriteria { max_iterations : 10, objective_tolerance : 1e-3, parameter_tolerance : 1e-6 };
+        assert_eq!(stopping_criteria.check(1,  1.0,  1.0), None);
+        assert_eq!(stopping_criteria.check(1,  1.0,  0.0), Some(StopReason::ParameterTolerance));
+        assert_eq!(stopping_criteria.check(1, -1e-4, 1.0), Some(StopReason::ObjectiveTolerance));
+        assert_eq!(stopping_criteria.check(10, 1.0,  1.0), Some(StopReason::MaxIterations));
+        assert_eq!(stopping_criteria.check(10, 0.0,  0.0), Some(StopReason::ParameterTolerance));
+
+        // NaN never satisfies a tolerance, so only the iteration limit can stop the algorithm.
+        assert_eq!(stopping_criteria.check(1,  f64::NAN, f64::NAN), None);
+        assert_eq!(stopping_criteria.check(10, f64::NAN, f64::NAN), Some(StopReason::MaxIterations));
+    }
+}
@@ -1,10 +1,44 @@
 use super::dendrogram::Dendrogram;
+use super::lance_williams::*;
+use crate::distance_matrix::DistanceMatrix;
+use crate::metric::Metric;
 
-/// Implementation of CLINK algorithm for single-linkage clustering.
+/// Complete-linkage clustering.
 ///
-/// A one-liner over-simplication of the SLINK algorithm I would give is that it is dynamic
-/// programming on "compressed-pointer" representation.
+/// This is exact complete-linkage clustering with the nearest-neighbour chain
+/// algorithm of [lance_williams_precomputed], which takes O(n^2) time and O(n^2) memory for the
+/// distance matrix. Defays' CLINK algorithm, which only takes O(n) memory but does not give exact
+/// complete-linkage clustering, is available as [clink_defays].
 pub fn clink<T, D>(samples : &[T], mut dissimilarity: D) -> Dendrogram
+where
+    D: FnMut(&T, &T) -> f64
+{
+    let mut distances = Vec::with_capacity(samples.len() * samples.len().saturating_sub(1) / 2);
+    for i in 0..samples.len() {
+        for j in i+1..samples.len() {
+            distances.push(dissimilarity(&samples[i], &samples[j]));
+        }
+    }
+    lance_williams_precomputed(&DistanceMatrix::from_condensed(samples.len(), distances), Linkage::Complete)
+}
+
+/// Implementation of Defays' CLINK algorithm.
+///
+/// Like SLINK for single-linkage clustering, this is dynamic programming on "compressed-pointer"
+/// representation, where each sample is inserted into the dendrogram of the samples before it.
+/// However, unlike SLINK, CLINK is only an approximation, and the result depends on the order of
+/// samples. Precisely, what is guaranteed is that:
+///  - Every cluster in a section at any height h has diameter, i.e. maximum dissimilarity between
+///    its samples, at most h.
+///  - Every merge height is the diameter of the cluster formed by the merge.
+///
+/// That is, the result is a valid complete-linkage hierarchy, but not necessarily the one which
+/// always merges the two clusters with the lowest complete-linkage distance. For example, inserting
+/// 0, 10, 1 and 11 in that order with absolute difference as dissimilarity merges 10 with {0, 1}
+/// at 10 instead of with 11 at 1, because 10 is already attached to {0, 1} when 11 is inserted.
+///
+/// See Defays, "An efficient algorithm for a complete link method", 1977.
+pub fn clink_defays<T, D>(samples : &[T], mut dissimilarity: D) -> Dendrogram
 where
     D: FnMut(&T, &T) -> f64
 {
@@ -12,6 +46,10 @@ where
     let mut lambdas = vec![0.0; samples.len()];
     let mut ms      = vec![0.0; samples.len()];
 
+    if samples.is_empty() {
+        return Dendrogram::new(lambdas, pis);
+    }
+
     // First element is special
     pis[0] = 0;
     lambdas[0] = f64::INFINITY;
@@ -87,3 +125,62 @@ where
     )
 }
 
+/// [clink] on precomputed distances.
+pub fn clink_precomputed(distance_matrix : &DistanceMatrix) -> Dendrogram {
+    lance_williams_precomputed(distance_matrix, Linkage::Complete)
+}
+
+/// [clink] with dissimilarity given by metric.
+pub fn clink_with_metric<P, M: Metric<P>>(samples : &[P], metric : &M) -> Dendrogram {
+    clink(samples, |a, b| metric.distance(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::naive::*;
+    use super::super::test_util::*;
+
+    use math::prelude::*;
+
+    #[test]
+    fn test_clink() {
+        for _ in 0..10 {
+            let samples = random_samples(30);
+            let dendrogram = clink(&samples, |a, b| a.distance(b));
+            for cluster_count in 1..=samples.len() {
+                let mut expected = vec![0; samples.len()];
+                for (label, cluster) in naive(samples.len(), cluster_count, complete_linkage(|i, j| samples[i].distance(&samples[j]))).into_iter().enumerate() {
+                    cluster.into_iter().for_each(|index| expected[index] = label);
+                }
+                assert_eq!(canonical(dendrogram.section_with_cluster_count(cluster_count).labels), canonical(expected));
+            }
+        }
+
+        assert!(clink::<f64, _>(&[], |a, b| (a - b).abs()).is_empty());
+        assert_eq!(clink(&[0.0], |a : &f64, b : &f64| (a - b).abs()).len(), 1);
+    }
+
+    #[test]
+    fn test_clink_defays() {
+        for _ in 0..10 {
+            let samples = random_samples(30);
+            let dendrogram = clink_defays(&samples, |a, b| a.distance(b));
+            for &height in dendrogram.merge_heights().iter().filter(|height| height.is_finite()) {
+                let labels = dendrogram.section_with_height(height).labels;
+                let diameters = (0..samples.len())
+                    .map(|i| (0..samples.len()).filter(|&j| labels[i] == labels[j]).map(|j| samples[i].distance(&samples[j])).fold(0.0, f64::max))
+                    .collect::<Vec<_>>();
+                assert!(diameters.iter().all(|&diameter| diameter <= height));
+                assert!(diameters.contains(&height));
+            }
+        }
+
+        let dendrogram = clink_defays(&[0.0, 10.0, 1.0, 11.0], |a : &f64, b : &f64| (a - b).abs());
+        assert_eq!(dendrogram.merge_heights(), &[1.0, 10.0, 11.0, f64::INFINITY]);
+        assert_eq!(canonical(dendrogram.section_with_cluster_count(2).labels), vec![0, 0, 0, 1]);
+        assert_eq!(canonical(clink(&[0.0, 10.0, 1.0, 11.0], |a : &f64, b : &f64| (a - b).abs()).section_with_cluster_count(2).labels), vec![0, 1, 0, 1]);
+
+        assert!(clink_defays::<f64, _>(&[], |a, b| (a - b).abs()).is_empty());
+    }
+}
@@ -29,19 +29,216 @@ impl Dendrogram {
         }
     }
 
-    /// Visualize dendrogram.
-    pub fn svg(&self, margin : f64, xscale : f64) -> DendrogramSvg<'_> {
-        DendrogramSvg {
-            dendrogram : self,
-            margin,
-            xscale,
+    /// Construct dendrogram from a sequence of merges.
+    ///
+    /// Each merge (index1, index2, height) joins the cluster containing sample index1 with the
+    /// cluster containing sample index2 at height. Merges are applied in the given order, and
+    /// merges between samples already in the same cluster are ignored. For example, the edges of a
+    /// minimum spanning tree sorted by weight give the single-linkage dendrogram.
+    pub fn from_merges<I>(sample_count : usize, merges : I) -> Self
+    where
+        I: IntoIterator<Item = (usize, usize, f64)>,
+    {
+        let mut merge_heights = vec![f64::INFINITY; sample_count];
+        let mut merge_targets = (0..sample_count).collect::<Vec<_>>();
+
+        // Last sample in the cluster of each root of the disjoint set.
+        let mut disjoint_set = DisjointSet::new(sample_count);
+        let mut lasts = (0..sample_count).collect::<Vec<_>>();
+        for (index1, index2, height) in merges {
+            let root1 = disjoint_set.find(index1);
+            let root2 = disjoint_set.find(index2);
+            if root1 != root2 {
+                let (last1, last2) = (lasts[root1], lasts[root2]);
+                let (first, last) = if last1 < last2 { (last1, last2) } else { (last2, last1) };
+                merge_heights[first] = height;
+                merge_targets[first] = last;
+
+                disjoint_set.merge(root1, root2);
+                lasts[disjoint_set.find(root1)] = last;
+            }
+        }
+
+        Self::new(merge_heights, merge_targets)
+    }
+
+    /// Construct dendrogram from a linkage matrix, as used by SciPy.
+    ///
+    /// Row k (cluster1, cluster2, height, size) merges cluster1 and cluster2 into a new cluster
+    /// with id sample_count + k, where ids less than sample_count are the samples themselves. Sizes
+    /// are not used. Heights lower than that of an earlier row cannot be represented, and are
+    /// raised to the height of the earlier row.
+    pub fn from_linkage_matrix<I>(sample_count : usize, linkage_matrix : I) -> Self
+    where
+        I: IntoIterator<Item = (usize, usize, f64, usize)>,
+    {
+        // Any sample of each cluster.
+        let mut representatives = (0..sample_count).collect::<Vec<_>>();
+        let mut merges = Vec::new();
+        let mut max_height = f64::NEG_INFINITY;
+        for (cluster1, cluster2, height, _) in linkage_matrix {
+            assert!(cluster1 < representatives.len() && cluster2 < representatives.len(), "linkage matrix refers to cluster that is not yet formed");
+            max_height = max_height.max(height);
+            merges.push((representatives[cluster1], representatives[cluster2], max_height));
+            representatives.push(representatives[cluster1]);
+        }
+        Self::from_merges(sample_count, merges)
+    }
+
+    /// Convert dendrogram to a linkage matrix, as used by SciPy.
+    ///
+    /// Row k (cluster1, cluster2, height, size) merges cluster1 and cluster2 into a new cluster
+    /// with id sample_count + k and size samples, where ids less than sample_count are the samples
+    /// themselves. Rows are sorted by height, and cluster1 < cluster2. There are sample_count - 1
+    /// rows, or fewer if some clusters are never merged.
+    pub fn linkage_matrix(&self) -> Vec<(usize, usize, f64, usize)> {
+        let sample_count = self.len();
+
+        // Id and size of the cluster of each root of the disjoint set.
+        let mut disjoint_set = DisjointSet::new(sample_count);
+        let mut ids = (0..sample_count).collect::<Vec<_>>();
+        let mut sizes = vec![1; sample_count];
+
+        let mut linkage_matrix = Vec::with_capacity(sample_count.saturating_sub(1));
+        for (index1, index2, height) in self.merges() {
+            let root1 = disjoint_set.find(index1);
+            let root2 = disjoint_set.find(index2);
+            let (id1, id2) = (ids[root1], ids[root2]);
+            let size = sizes[root1] + sizes[root2];
+            linkage_matrix.push((id1.min(id2), id1.max(id2), height, size));
+
+            disjoint_set.merge(root1, root2);
+            let root = disjoint_set.find(root1);
+            ids[root] = sample_count + linkage_matrix.len() - 1;
+            sizes[root] = size;
+        }
+        linkage_matrix
+    }
+
+    /// Convert dendrogram to Newick format, with given labels for the samples.
+    ///
+    /// Branch lengths are differences in height. If some clusters are never merged, they are joined
+    /// under a root without branch lengths. Labels are quoted if they contain characters with
+    /// special meaning in Newick format.
+    pub fn newick<L: std::fmt::Display>(&self, labels : &[L]) -> String {
+        assert_eq!(labels.len(), self.len());
+
+        let sample_count = self.len();
+        let (linkage_matrix, roots) = self.tree();
+        let height = |id : usize| if id < sample_count { 0.0 } else { linkage_matrix[id - sample_count].2 };
+
+        // Explicit stack instead of recursion, since dendrograms can be as deep as there are
+        // samples.
+        enum Token { Node(usize, f64), Separator, Close(usize, f64) }
+        let mut stack = Vec::new();
+        if roots.len() > 1 {
+            stack.push(Token::Close(usize::MAX, f64::NAN));
+        }
+        for (index, &root) in roots.iter().enumerate().rev() {
+            stack.push(Token::Node(root, f64::NAN));
+            if index != 0 {
+                stack.push(Token::Separator);
+            }
+        }
+
+        let mut result = String::new();
+        if roots.len() > 1 {
+            result.push('(');
+        }
+        while let Some(token) = stack.pop() {
+            let (id, parent_height) = match token {
+                Token::Separator => { result.push(','); continue }
+                Token::Node(id, parent_height) if id < sample_count => {
+                    result.push_str(&newick_label(&labels[id].to_string()));
+                    (id, parent_height)
+                }
+                Token::Node(id, parent_height) => {
+                    let (id1, id2, height, _) = linkage_matrix[id - sample_count];
+                    stack.push(Token::Close(id, parent_height));
+                    stack.push(Token::Node(id2, height));
+                    stack.push(Token::Separator);
+                    stack.push(Token::Node(id1, height));
+                    result.push('(');
+                    continue
+                }
+                Token::Close(id, parent_height) => { result.push(')'); (id, parent_height) }
+            };
+
+            // Roots have no parent height, and so no branch length.
+            if parent_height.is_finite() && height(id).is_finite() {
+                result.push_str(&format!(":{}", parent_height - height(id)));
+            }
+        }
+        result.push(';');
+        result
+    }
+
+    /// Order of samples such that every cluster in every section is contiguous, so that the
+    /// dendrogram can be drawn without crossing.
+    pub fn leaf_order(&self) -> Vec<usize> {
+        let (linkage_matrix, roots) = self.tree();
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack = roots.into_iter().rev().collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            if id < self.len() {
+                order.push(id);
+            } else {
+                let (id1, id2, _, _) = linkage_matrix[id - self.len()];
+                stack.push(id2);
+                stack.push(id1);
+            }
         }
+        order
+    }
+
+    /// Linkage matrix, and ids of clusters that are never merged.
+    fn tree(&self) -> (Vec<(usize, usize, f64, usize)>, Vec<usize>) {
+        let linkage_matrix = self.linkage_matrix();
+        let mut merged = vec![false; self.len() + linkage_matrix.len()];
+        for &(id1, id2, _, _) in &linkage_matrix {
+            merged[id1] = true;
+            merged[id2] = true;
+        }
+        let roots = (0..merged.len()).filter(|&id| !merged[id]).collect();
+        (linkage_matrix, roots)
+    }
+
+    /// Merges (sample1, sample2, height) in the pointer representation, sorted by height.
+    fn merges(&self) -> Vec<(usize, usize, f64)> {
+        let mut merges = std::iter::zip(&self.merge_heights, &self.merge_targets)
+            .enumerate()
+            .filter(|&(item, (_, &merge_target))| item != merge_target)
+            .map(|(item, (&merge_height, &merge_target))| (item, merge_target, merge_height))
+            .collect::<Vec<_>>();
+        merges.sort_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b));
+        merges
+    }
+
+    /// Visualize dendrogram, see [DendrogramSvg] for options.
+    pub fn svg(&self) -> DendrogramSvg<'_> {
+        DendrogramSvg::new(self)
+    }
+
+    /// Lowest height at which each sample is no longer the last sample in its cluster, or
+    /// infinity for the last sample.
+    pub fn merge_heights(&self) -> &[f64] {
+        &self.merge_heights
+    }
+
+    /// Last sample of the cluster each sample merges into at its merge height, or itself for the
+    /// last sample.
+    pub fn merge_targets(&self) -> &[usize] {
+        &self.merge_targets
     }
 
     pub fn len(&self) -> usize {
         self.merge_heights.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.merge_heights.is_empty()
+    }
+
     /// Return section at ```height```.
     pub fn section_with_height(&self, height : f64) -> DendrogramSection {
         let mut disjoint_set = DisjointSet::new(self.len());
@@ -67,40 +264,196 @@ impl Dendrogram {
             .sorted_by(|(_, (merge_height1, _)), (_, (merge_height2, _))| f64::partial_cmp(merge_height1, merge_height2).unwrap())
             .map(|(item, (_, &merge_target))| (item, merge_target))
         {
-            edges.push((index1, index2));
-            disjoint_set.merge(index1, index2);
             if disjoint_set.connected_component_count() <= cluster_count {
                 break
             }
+            edges.push((index1, index2));
+            disjoint_set.merge(index1, index2);
         }
         let labels = disjoint_set.connceted_component_labels();
         DendrogramSection { edges, labels, }
     }
 }
 
+/// Quote label for Newick format if needed.
+fn newick_label(label : &str) -> String {
+    if label.chars().any(|c| c.is_whitespace() || "()[]':;,".contains(c)) {
+        format!("'{}'", label.replace('\'', "''"))
+    } else {
+        label.to_string()
+    }
+}
+
+/// Orientation of dendrogram.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    /// Leaves at the bottom, with height increasing upwards.
+    #[default]
+    Vertical,
+    /// Leaves on the left, with height increasing rightwards.
+    Horizontal,
+}
+
+/// Colors of clusters below cut height, which are the same as the default colors of matplotlib.
+const CLUSTER_COLORS : [&str; 10] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f", "#bcbd22", "#17becf"];
+
 /// Svg representation of dendrogram.
+///
+/// Samples are drawn in [Dendrogram::leaf_order], and every merge is drawn as a U-shaped link
+/// between the two merged clusters at the merge height. Infinite merge heights are drawn at the top
+/// of the height axis.
+#[derive(Debug, Clone)]
 pub struct DendrogramSvg<'a> {
-    dendrogram : &'a Dendrogram,
-    margin : f64,
-    xscale : f64,
+    pub dendrogram : &'a Dendrogram,
+    /// Labels of samples, drawn next to the leaves.
+    pub labels : Option<Vec<String>>,
+    /// Links of clusters in the section at cut height are colored by cluster, and the cut height
+    /// is marked by a dashed line.
+    pub cut_height : Option<f64>,
+    pub orientation : Orientation,
+    /// Distance between adjacent leaves.
+    pub leaf_spacing : f64,
+    /// Length of height axis.
+    pub axis_length : f64,
+    /// Space around dendrogram for labels and axis.
+    pub margin : f64,
+}
+
+impl<'a> DendrogramSvg<'a> {
+    /// Constructor with default options, which are vertical orientation without labels or cut
+    /// height.
+    pub fn new(dendrogram : &'a Dendrogram) -> Self {
+        Self {
+            dendrogram,
+            labels : None,
+            cut_height : None,
+            orientation : Orientation::Vertical,
+            leaf_spacing : 20.0,
+            axis_length : 400.0,
+            margin : 60.0,
+        }
+    }
+
+    /// Maximum height on axis, which is rounded up to a multiple of the tick step.
+    fn axis_max_height(&self) -> (f64, f64) {
+        let max_height = self.dendrogram.merge_heights.iter()
+            .copied()
+            .filter(|height| height.is_finite())
+            .chain(self.cut_height.filter(|height| height.is_finite()))
+            .fold(0.0, f64::max);
+        if max_height <= 0.0 {
+            return (1.0, 0.2);
+        }
+
+        // Step of 1, 2 or 5 times a power of ten, with at most 5 steps.
+        let magnitude = 10f64.powf((max_height / 5.0).log10().floor());
+        let step = [1.0, 2.0, 5.0, 10.0].into_iter().map(|factor| factor * magnitude).find(|step| max_height / step <= 5.0).unwrap();
+        ((max_height / step).ceil() * step, step)
+    }
+
+    /// Svg coordinates of a point at position along leaves and height.
+    fn point(&self, position : f64, height : f64, max_height : f64) -> (f64, f64) {
+        let height = if height.is_finite() { height.min(max_height) / max_height * self.axis_length } else { self.axis_length };
+        match self.orientation {
+            Orientation::Vertical   => (position, self.axis_length - height),
+            Orientation::Horizontal => (height, position),
+        }
+    }
 }
 
 impl std::fmt::Display for DendrogramSvg<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let width  = self.dendrogram.merge_heights.len() as f64 * self.xscale;
-        let height = self.dendrogram.merge_heights[..self.dendrogram.merge_heights.len()-1].iter().copied().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
+        let sample_count = self.dendrogram.len();
+        let (linkage_matrix, _) = self.dendrogram.tree();
+        let leaf_order = self.dendrogram.leaf_order();
+        let (max_height, step) = self.axis_max_height();
 
-        writeln!(f, "<svg width=\"100%\" height=\"100%\" viewBox=\"{x} {y} {width} {height}\">", x=-self.margin, y=-self.margin, width=width+self.margin, height = height+self.margin)?;
-        writeln!(f, "<rect fill=\"#ffffff\" stroke=\"#ffffff\" x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\"/>", x=-self.margin, y=-self.margin, width=width+self.margin, height = height+self.margin)?;
+        // Position along leaves, height, and first leaf of each cluster, where the first cluster
+        // of each merge comes first in leaf order.
+        let mut positions = vec![0.0; sample_count + linkage_matrix.len()];
+        let mut heights   = vec![0.0; sample_count + linkage_matrix.len()];
+        let mut firsts    = (0..sample_count + linkage_matrix.len()).collect::<Vec<_>>();
+        for (index, &leaf) in leaf_order.iter().enumerate() {
+            positions[leaf] = (index as f64 + 0.5) * self.leaf_spacing;
+        }
+        for (row, &(id1, id2, height, _)) in linkage_matrix.iter().enumerate() {
+            positions[sample_count + row] = (positions[id1] + positions[id2]) / 2.0;
+            heights[sample_count + row] = height;
+            firsts[sample_count + row] = firsts[id1];
+        }
 
-        for (item, (&merge_height, &merge_target)) in std::iter::zip(&self.dendrogram.merge_heights, &self.dendrogram.merge_targets).enumerate() {
-            let x1 = item         as f64 * self.xscale;
-            let x2 = merge_target as f64 * self.xscale;
+        // Colors of clusters in the section at cut height, assigned in leaf order. Clusters of
+        // single samples have no links, and so are skipped.
+        let mut colors = vec![None; sample_count];
+        if let Some(cut_height) = self.cut_height {
+            let labels = self.dendrogram.section_with_height(cut_height).labels;
+            let counts = labels.iter().copied().counts();
+            let mut cluster_colors = std::collections::HashMap::new();
+            for &leaf in leaf_order.iter().filter(|&&leaf| counts[&labels[leaf]] > 1) {
+                let next = cluster_colors.len();
+                colors[leaf] = Some(CLUSTER_COLORS[*cluster_colors.entry(labels[leaf]).or_insert(next) % CLUSTER_COLORS.len()]);
+            }
+        }
 
-            let y1 = height - 0.0;
-            let y2 = height - merge_height.is_finite().then_some(merge_height).unwrap_or(height);
+        let length = sample_count as f64 * self.leaf_spacing;
+        let (width, height) = match self.orientation {
+            Orientation::Vertical   => (length, self.axis_length),
+            Orientation::Horizontal => (self.axis_length, length),
+        };
+        writeln!(f, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"100%\" height=\"100%\" viewBox=\"{x} {y} {width} {height}\">", x=-self.margin, y=-self.margin, width=width+2.0*self.margin, height=height+2.0*self.margin)?;
+        writeln!(f, "<rect fill=\"#ffffff\" stroke=\"#ffffff\" x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\"/>", x=-self.margin, y=-self.margin, width=width+2.0*self.margin, height=height+2.0*self.margin)?;
 
-            writeln!(f, "<polyline points=\"{x1},{y1} {x1},{y2} {x2},{y2}\" fill=\"none\" stroke=\"black\"/>")?;
+        // Links.
+        for (row, &(id1, id2, merge_height, _)) in linkage_matrix.iter().enumerate() {
+            let color = match self.cut_height {
+                Some(cut_height) if merge_height <= cut_height => colors[firsts[sample_count + row]].unwrap(),
+                _ => "#000000",
+            };
+            let (x1, y1) = self.point(positions[id1], heights[id1], max_height);
+            let (x2, y2) = self.point(positions[id1], merge_height, max_height);
+            let (x3, y3) = self.point(positions[id2], merge_height, max_height);
+            let (x4, y4) = self.point(positions[id2], heights[id2], max_height);
+            writeln!(f, "<polyline points=\"{x1},{y1} {x2},{y2} {x3},{y3} {x4},{y4}\" fill=\"none\" stroke=\"{color}\"/>")?;
+        }
+
+        // Labels.
+        if let Some(labels) = &self.labels {
+            assert_eq!(labels.len(), sample_count);
+            for (leaf, label) in labels.iter().enumerate() {
+                let (x, y) = self.point(positions[leaf], 0.0, max_height);
+                let label = label.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                match self.orientation {
+                    Orientation::Vertical   => writeln!(f, "<text x=\"{x}\" y=\"{y}\" transform=\"rotate(-90 {x} {y}) translate(-5 0)\" text-anchor=\"end\" dominant-baseline=\"middle\" font-size=\"10\">{label}</text>")?,
+                    Orientation::Horizontal => writeln!(f, "<text x=\"{x}\" y=\"{y}\" transform=\"translate(-5 0)\" text-anchor=\"end\" dominant-baseline=\"middle\" font-size=\"10\">{label}</text>")?,
+                }
+            }
+        }
+
+        // Height axis, after the last leaf.
+        let precision = (-step.log10().floor()).max(0.0) as usize;
+        let (x1, y1) = self.point(length, 0.0, max_height);
+        let (x2, y2) = self.point(length, max_height, max_height);
+        writeln!(f, "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"#000000\"/>")?;
+        for tick in 0..=(max_height / step).round() as usize {
+            let tick_height = tick as f64 * step;
+            let (x, y) = self.point(length, tick_height, max_height);
+            match self.orientation {
+                Orientation::Vertical => {
+                    writeln!(f, "<line x1=\"{x}\" y1=\"{y}\" x2=\"{x2}\" y2=\"{y}\" stroke=\"#000000\"/>", x2=x+5.0)?;
+                    writeln!(f, "<text x=\"{x}\" y=\"{y}\" transform=\"translate(8 0)\" dominant-baseline=\"middle\" font-size=\"10\">{tick_height:.precision$}</text>")?;
+                }
+                Orientation::Horizontal => {
+                    writeln!(f, "<line x1=\"{x}\" y1=\"{y}\" x2=\"{x}\" y2=\"{y2}\" stroke=\"#000000\"/>", y2=y+5.0)?;
+                    writeln!(f, "<text x=\"{x}\" y=\"{y}\" transform=\"translate(0 15)\" text-anchor=\"middle\" font-size=\"10\">{tick_height:.precision$}</text>")?;
+                }
+            }
+        }
+
+        // Cut height.
+        if let Some(cut_height) = self.cut_height {
+            let (x1, y1) = self.point(0.0, cut_height, max_height);
+            let (x2, y2) = self.point(length, cut_height, max_height);
+            writeln!(f, "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"#808080\" stroke-dasharray=\"4\"/>")?;
         }
 
         writeln!(f, "</svg>")?;
@@ -108,4 +461,111 @@ impl std::fmt::Display for DendrogramSvg<'_> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::slink::slink;
+    use super::super::test_util::random_samples;
+
+    use math::prelude::*;
+
+    #[test]
+    fn test_section_with_cluster_count() {
+        let samples = random_samples(20);
+        let dendrogram = slink(&samples, |a, b| a.distance(b));
+        for cluster_count in 1..=samples.len() {
+            let section = dendrogram.section_with_cluster_count(cluster_count);
+            assert_eq!(section.labels.iter().unique().count(), cluster_count);
+            assert_eq!(section.edges.len(), samples.len() - cluster_count);
+        }
+    }
+
+    #[test]
+    fn test_from_merges() {
+        let samples = random_samples(50);
+
+        // Kruskal's algorithm gives the edges of minimum spanning tree in the order of merges.
+        let mut edges = (0..samples.len()).flat_map(|a| (a+1..samples.len()).map(move |b| (a, b))).map(|(a, b)| (a, b, samples[a].distance(&samples[b]))).collect::<Vec<_>>();
+        edges.sort_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b));
+
+        let expected = slink(&samples, |a, b| a.distance(b));
+        let dendrogram = Dendrogram::from_merges(samples.len(), edges);
+        for cluster_count in 1..=samples.len() {
+            assert_eq!(dendrogram.section_with_cluster_count(cluster_count).labels, expected.section_with_cluster_count(cluster_count).labels);
+        }
+    }
+
+    #[test]
+    fn test_linkage_matrix() {
+        let dendrogram = slink(&[0.0, 1.0, 6.0, 3.0], |a : &f64, b : &f64| (a - b).abs());
+        assert_eq!(dendrogram.linkage_matrix(), vec![(0, 1, 1.0, 2), (3, 4, 2.0, 3), (2, 5, 3.0, 4)]);
+
+        let samples = random_samples(50);
+        let dendrogram = slink(&samples, |a, b| a.distance(b));
+        let linkage_matrix = dendrogram.linkage_matrix();
+        assert_eq!(linkage_matrix.len(), samples.len() - 1);
+        assert_eq!(linkage_matrix.last().unwrap().3, samples.len());
+
+        let converted = Dendrogram::from_linkage_matrix(samples.len(), linkage_matrix.iter().copied());
+        assert_eq!(converted.linkage_matrix(), linkage_matrix);
+        for cluster_count in 1..=samples.len() {
+            assert_eq!(converted.section_with_cluster_count(cluster_count).labels, dendrogram.section_with_cluster_count(cluster_count).labels);
+        }
+
+        // Clusters that are never merged.
+        let dendrogram = Dendrogram::from_merges(4, [(2, 3, 1.0)]);
+        assert_eq!(dendrogram.linkage_matrix(), vec![(2, 3, 1.0, 2)]);
+        assert_eq!(Dendrogram::from_linkage_matrix(4, [(2, 3, 1.0, 2)]).merge_heights(), dendrogram.merge_heights());
+
+        assert!(Dendrogram::from_linkage_matrix(0, []).linkage_matrix().is_empty());
+        assert!(Dendrogram::from_linkage_matrix(1, []).linkage_matrix().is_empty());
+    }
 
+    #[test]
+    fn test_newick() {
+        let dendrogram = slink(&[0.0, 1.0, 5.0], |a : &f64, b : &f64| (a - b).abs());
+        assert_eq!(dendrogram.newick(&[0, 1, 2]), "(2:4,(0:1,1:1):3);");
+        assert_eq!(dendrogram.newick(&["a", "b c", "d'e"]), "('d''e':4,(a:1,'b c':1):3);");
+
+        let dendrogram = Dendrogram::from_merges(4, [(2, 3, 1.0)]);
+        assert_eq!(dendrogram.newick(&[0, 1, 2, 3]), "(0,1,(2:1,3:1));");
+
+        assert_eq!(Dendrogram::from_merges(1, []).newick(&["a"]), "a;");
+        assert_eq!(Dendrogram::from_merges(0, []).newick::<usize>(&[]), ";");
+    }
+
+    #[test]
+    fn test_leaf_order() {
+        let samples = random_samples(50);
+        let dendrogram = slink(&samples, |a, b| a.distance(b));
+        let leaf_order = dendrogram.leaf_order();
+        assert_eq!(leaf_order.iter().copied().sorted().collect::<Vec<_>>(), (0..samples.len()).collect::<Vec<_>>());
+
+        // Every cluster in every section is contiguous.
+        for cluster_count in 1..=samples.len() {
+            let labels = dendrogram.section_with_cluster_count(cluster_count).labels;
+            let runs = leaf_order.iter().map(|&leaf| labels[leaf]).dedup().collect::<Vec<_>>();
+            assert_eq!(runs.len(), cluster_count);
+        }
+
+        assert_eq!(Dendrogram::from_merges(4, [(2, 3, 1.0)]).leaf_order(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_svg() {
+        let dendrogram = slink(&[0.0, 1.0, 6.0, 3.0], |a : &f64, b : &f64| (a - b).abs());
+        let svg = DendrogramSvg { labels : Some(vec!["a".to_string(), "b".to_string(), "c".to_string(), "<d>".to_string()]), cut_height : Some(2.5), ..dendrogram.svg() }.to_string();
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert_eq!(svg.matches("stroke=\"#1f77b4\"").count(), 2);
+        assert!(svg.contains("&lt;d&gt;</text>"));
+
+        let svg = DendrogramSvg { orientation : Orientation::Horizontal, ..dendrogram.svg() }.to_string();
+        assert_eq!(svg.matches("<polyline").count(), 3);
+
+        // Degenerate dendrograms.
+        assert!(Dendrogram::from_merges(0, []).svg().to_string().ends_with("</svg>\n"));
+        assert!(Dendrogram::from_merges(1, []).svg().to_string().ends_with("</svg>\n"));
+        assert!(Dendrogram::from_merges(2, [(0, 1, 0.0)]).svg().to_string().ends_with("</svg>\n"));
+        assert!(Dendrogram::new(vec![f64::INFINITY; 2], vec![1, 1]).svg().to_string().ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,419 @@
+//! Generic agglomerative clustering with the Lance–Williams update.
+//!
+//! Distances between the merged cluster and every other cluster are computed from distances
+//! before the merge, so that only a single distance matrix of O(n^2) memory is needed for any of
+//! the supported [Linkage] criteria. Reducible linkages are merged with the nearest-neighbour
+//! chain algorithm in O(n^2) time. Centroid and median linkage are not reducible, and are merged
+//! with a priority queue of nearest neighbours instead.
+//!
+//! See Müllner, "Modern hierarchical, agglomerative clustering algorithms", 2011.
+
+use super::dendrogram::Dendrogram;
+use crate::distance_matrix::DistanceMatrix;
+
+use clap::ValueEnum;
+
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Linkage criterion, i.e. distance between clusters.
+///
+/// Centroid, median and Ward linkage are only meaningful for euclidean distances between
+/// samples. As in SciPy, distances are squared before the Lance–Williams update, and merge
+/// heights are square roots, so that they are in the same unit as the input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Linkage {
+    /// Minimum distance between samples of the two clusters.
+    Single,
+    /// Maximum distance between samples of the two clusters.
+    Complete,
+    /// Mean distance between samples of the two clusters, also known as UPGMA.
+    Average,
+    /// Mean of distances from the two clusters merged into a cluster, also known as WPGMA.
+    Weighted,
+    /// Distance between centroids of the two clusters, also known as UPGMC.
+    Centroid,
+    /// Distance between centers of the two clusters, where the center of a merged cluster is the
+    /// midpoint of the centers it is merged from, also known as WPGMC.
+    Median,
+    /// Ward's minimum variance criterion, where the squared distance is proportional to the
+    /// increase in within-cluster sum of squares caused by a merge.
+    Ward,
+}
+
+impl Linkage {
+    /// Return true if merging two clusters never brings the merged cluster closer to another
+    /// cluster than both of them were, which is required by the nearest-neighbour chain algorithm.
+    pub fn is_reducible(self) -> bool {
+        !matches!(self, Self::Centroid | Self::Median)
+    }
+
+    fn is_squared(self) -> bool {
+        matches!(self, Self::Centroid | Self::Median | Self::Ward)
+    }
+
+    /// Distance between cluster k and the union of clusters i and j.
+    fn update(self, distance_ik : f64, distance_jk : f64, distance_ij : f64, size_i : usize, size_j : usize, size_k : usize) -> f64 {
+        let (size_i, size_j, size_k) = (size_i as f64, size_j as f64, size_k as f64);
+        match self {
+            Self::Single   => distance_ik.min(distance_jk),
+            Self::Complete => distance_ik.max(distance_jk),
+            Self::Average  => (size_i * distance_ik + size_j * distance_jk) / (size_i + size_j),
+            Self::Weighted => 0.5 * (distance_ik + distance_jk),
+            Self::Centroid => {
+                let size = size_i + size_j;
+                (size_i * distance_ik + size_j * distance_jk) / size - size_i * size_j * distance_ij / (size * size)
+            }
+            Self::Median   => 0.5 * (distance_ik + distance_jk) - 0.25 * distance_ij,
+            Self::Ward     => ((size_i + size_k) * distance_ik + (size_j + size_k) * distance_jk - size_k * distance_ij) / (size_i + size_j + size_k),
+        }
+    }
+}
+
+/// Agglomerative clustering with any [Linkage] criterion.
+///
+/// **Example**: `lance_williams(&samples, |a, b| a.distance(b), Linkage::Average)`
+pub fn lance_williams<T, D>(samples : &[T], dissimilarity : D, linkage : Linkage) -> Dendrogram
+where
+    T: Sync,
+    D: Fn(&T, &T) -> f64 + Sync,
+{
+    lance_williams_precomputed(&DistanceMatrix::from_samples(samples, dissimilarity), linkage)
+}
+
+/// [lance_williams] on precomputed distances.
+///
+/// Centroid and median linkage may produce inversions, where a merge is lower than an earlier
+/// merge that formed one of its clusters. Since a [Dendrogram] is cut by height, such a merge is
+/// recorded at the height of the earlier merge instead.
+pub fn lance_williams_precomputed(distance_matrix : &DistanceMatrix, linkage : Linkage) -> Dendrogram {
+    let sample_count = distance_matrix.sample_count();
+    let mut merges = merges(distance_matrix, linkage, linkage.is_reducible());
+
+    if linkage.is_reducible() {
+        // The nearest-neighbour chain algorithm finds merges out of order, but heights are
+        // monotone along each branch, so that a stable sort by height gives a valid order.
+        merges.sort_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b));
+    } else {
+        let mut height = f64::NEG_INFINITY;
+        for (_, _, merge_height) in &mut merges {
+            height = height.max(*merge_height);
+            *merge_height = height;
+        }
+    }
+
+    Dendrogram::from_merges(sample_count, merges)
+}
+
+/// Sequence of merges (sample1, sample2, height) with a representative sample of each cluster.
+fn merges(distance_matrix : &DistanceMatrix, linkage : Linkage, nn_chain : bool) -> Vec<(usize, usize, f64)> {
+    let mut distance_matrix = distance_matrix.clone();
+    let sample_count = distance_matrix.sample_count();
+    if linkage.is_squared() {
+        for i in 0..sample_count {
+            for j in i+1..sample_count {
+                let distance = distance_matrix.get(i, j);
+                distance_matrix.set(i, j, distance * distance);
+            }
+        }
+    }
+
+    let mut merges = if nn_chain { nn_chain_merges(distance_matrix, linkage) } else { priority_queue_merges(distance_matrix, linkage) };
+    if linkage.is_squared() {
+        for (_, _, height) in &mut merges {
+            *height = height.sqrt();
+        }
+    }
+    merges
+}
+
+/// Active clusters during agglomeration.
+///
+/// Each cluster is identified by a slot, and the merged cluster takes the slot of one of the
+/// clusters it is merged from.
+struct Clusters {
+    distance_matrix : DistanceMatrix,
+    linkage : Linkage,
+    actives : Vec<bool>,
+    sizes : Vec<usize>,
+}
+
+impl Clusters {
+    fn new(distance_matrix : DistanceMatrix, linkage : Linkage) -> Self {
+        let sample_count = distance_matrix.sample_count();
+        Self { distance_matrix, linkage, actives : vec![true; sample_count], sizes : vec![1; sample_count] }
+    }
+
+    /// Merge cluster i into cluster j, and return merge (i, j, height).
+    fn merge(&mut self, i : usize, j : usize) -> (usize, usize, f64) {
+        let distance_ij = self.distance_matrix.get(i, j);
+        self.actives[i] = false;
+        for k in 0..self.actives.len() {
+            if self.actives[k] && k != j {
+                let distance = self.linkage.update(self.distance_matrix.get(i, k), self.distance_matrix.get(j, k), distance_ij, self.sizes[i], self.sizes[j], self.sizes[k]);
+                self.distance_matrix.set(j, k, distance);
+            }
+        }
+        self.sizes[j] += self.sizes[i];
+        (i, j, distance_ij)
+    }
+
+    /// Nearest active cluster to cluster i, preferring the lowest slot in case of ties.
+    fn nearest(&self, i : usize) -> Option<(usize, f64)> {
+        (0..self.actives.len())
+            .filter(|&k| self.actives[k] && k != i)
+            .map(|k| (k, self.distance_matrix.get(i, k)))
+            .min_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+    }
+}
+
+/// Nearest-neighbour chain algorithm, which is only correct for reducible linkages.
+///
+/// Follow nearest neighbours from an arbitrary cluster until two clusters are nearest neighbours
+/// of each other, which can then be merged. Reducibility guarantees that the rest of the chain
+/// remains valid after the merge.
+///
+/// Clusters are slots 0..sample_count of state, where distance(state, i, k) is the distance between
+/// active clusters i and k, and merge(state, i, j) merges cluster i into cluster j, after which
+/// only j remains active. Return merges (i, j, distance) in the order they are performed.
+pub(crate) fn nn_chain<S, D, M>(sample_count : usize, state : &mut S, distance : D, mut merge : M) -> Vec<(usize, usize, f64)>
+where
+    D: Fn(&S, usize, usize) -> f64,
+    M: FnMut(&mut S, usize, usize),
+{
+    let mut actives = vec![true; sample_count];
+    let mut merges = Vec::with_capacity(sample_count.saturating_sub(1));
+    let mut chain = Vec::new();
+    for _ in 1..sample_count {
+        if chain.is_empty() {
+            chain.push(actives.iter().position(|&active| active).unwrap());
+        }
+
+        let (i, j, height) = loop {
+            let i = chain[chain.len() - 1];
+            let previous = chain.len().checked_sub(2).map(|index| chain[index]);
+
+            // Prefer the lowest slot in case of ties, but the previous cluster in the chain over
+            // anything else, so that the chain cannot cycle.
+            let (mut nearest, mut nearest_distance) = (0..sample_count)
+                .filter(|&k| actives[k] && k != i)
+                .map(|k| (k, distance(state, i, k)))
+                .min_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+                .unwrap();
+            if let Some(previous) = previous {
+                let previous_distance = distance(state, i, previous);
+                if previous_distance <= nearest_distance {
+                    (nearest, nearest_distance) = (previous, previous_distance);
+                }
+            }
+
+            if Some(nearest) == previous {
+                chain.truncate(chain.len() - 2);
+                break (i, nearest, nearest_distance);
+            }
+            chain.push(nearest);
+        };
+
+        merge(state, i, j);
+        actives[i] = false;
+        merges.push((i, j, height));
+    }
+    merges
+}
+
+/// [nn_chain] on a distance matrix updated with the Lance-Williams formula.
+fn nn_chain_merges(distance_matrix : DistanceMatrix, linkage : Linkage) -> Vec<(usize, usize, f64)> {
+    let sample_count = distance_matrix.sample_count();
+    let mut clusters = Clusters::new(distance_matrix, linkage);
+    nn_chain(sample_count, &mut clusters,
+        |clusters, i, k| clusters.distance_matrix.get(i, k),
+        |clusters, i, j| { clusters.merge(i, j); },
+    )
+}
+
+/// Generic algorithm with a priority queue of nearest neighbours, which is correct for any linkage.
+fn priority_queue_merges(distance_matrix : DistanceMatrix, linkage : Linkage) -> Vec<(usize, usize, f64)> {
+    let sample_count = distance_matrix.sample_count();
+    let mut clusters = Clusters::new(distance_matrix, linkage);
+    let mut merges = Vec::with_capacity(sample_count.saturating_sub(1));
+
+    // Nearest neighbour of each active cluster. Entries in the queue are lazily invalidated when
+    // they no longer match.
+    let mut nearests = (0..sample_count).map(|i| clusters.nearest(i)).collect::<Vec<_>>();
+    let mut queue = nearests.iter().enumerate()
+        .filter_map(|(i, nearest)| nearest.map(|(_, distance)| Reverse(Entry { distance, slot : i })))
+        .collect::<BinaryHeap<_>>();
+
+    while let Some(Reverse(Entry { distance, slot : i })) = queue.pop() {
+        let Some((j, nearest_distance)) = nearests[i] else { continue };
+        if !clusters.actives[i] || nearest_distance.to_bits() != distance.to_bits() {
+            continue;
+        }
+
+        merges.push(clusters.merge(i, j));
+        nearests[i] = None;
+
+        // Nearest neighbours only change for clusters whose nearest neighbour was involved in the
+        // merge, or which are now closer to the merged cluster.
+        for k in 0..sample_count {
+            if !clusters.actives[k] {
+                continue;
+            }
+
+            let update = match nearests[k] {
+                Some((nearest, _)) if k == j || nearest == i || nearest == j => clusters.nearest(k),
+                Some((_, nearest_distance)) if clusters.distance_matrix.get(j, k) < nearest_distance => Some((j, clusters.distance_matrix.get(j, k))),
+                _ => continue,
+            };
+            nearests[k] = update;
+            if let Some((_, distance)) = update {
+                queue.push(Reverse(Entry { distance, slot : k }));
+            }
+        }
+    }
+    merges
+}
+
+/// Entry in priority queue ordered by distance and then by slot.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    distance : f64,
+    slot : usize,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other : &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other : &Self) -> Ordering {
+        f64::total_cmp(&self.distance, &other.distance).then(self.slot.cmp(&other.slot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::naive::*;
+    use super::super::slink::slink;
+    use super::super::test_util::random_samples;
+
+    use math::prelude::*;
+
+    const LINKAGES : [Linkage; 7] = [Linkage::Single, Linkage::Complete, Linkage::Average, Linkage::Weighted, Linkage::Centroid, Linkage::Median, Linkage::Ward];
+
+    fn sorted_heights(mut merges : Vec<(usize, usize, f64)>) -> Vec<f64> {
+        merges.sort_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b));
+        merges.into_iter().map(|(_, _, height)| height).collect()
+    }
+
+    fn assert_heights_close(a : &[f64], b : &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in std::iter::zip(a, b) {
+            assert!((a - b).abs() < 1e-9, "{a} != {b}");
+        }
+    }
+
+    /// Merge heights by brute force over clusters of samples.
+    fn brute_force_heights<D>(sample_count : usize, mut distance : D) -> Vec<f64>
+    where
+        D: FnMut(&[usize], &[usize]) -> f64
+    {
+        let mut clusters = (0..sample_count).map(|index| vec![index]).collect::<Vec<_>>();
+        let mut heights = Vec::new();
+        while clusters.len() > 1 {
+            let (i, j, height) = (0..clusters.len())
+                .flat_map(|i| (i+1..clusters.len()).map(move |j| (i, j)))
+                .map(|(i, j)| (i, j, distance(&clusters[i], &clusters[j])))
+                .min_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b))
+                .unwrap();
+            let cluster = clusters.swap_remove(j);
+            clusters[i].extend_from_slice(&cluster);
+            heights.push(height);
+        }
+        heights
+    }
+
+    fn centroid(samples : &[Vector<2>], cluster : &[usize]) -> Vector<2> {
+        cluster.iter().fold(Vector::zero(), |sum, &index| sum + samples[index]) / cluster.len() as f64
+    }
+
+    #[test]
+    fn test_nn_chain() {
+        // Both algorithms agree on reducible linkages.
+        let samples = random_samples(60);
+        let distance_matrix = DistanceMatrix::from_samples(&samples, |a, b| a.distance(b));
+        for linkage in LINKAGES.into_iter().filter(|linkage| linkage.is_reducible()) {
+            assert_heights_close(&sorted_heights(merges(&distance_matrix, linkage, true)), &sorted_heights(merges(&distance_matrix, linkage, false)));
+        }
+    }
+
+    #[test]
+    fn test_brute_force() {
+        let samples = random_samples(40);
+        let distance_matrix = DistanceMatrix::from_samples(&samples, |a, b| a.distance(b));
+        let distance = |i : usize, j : usize| samples[i].distance(&samples[j]);
+
+        let ward = |a : &[usize], b : &[usize]| {
+            let (size_a, size_b) = (a.len() as f64, b.len() as f64);
+            (2.0 * size_a * size_b / (size_a + size_b)).sqrt() * centroid(&samples, a).distance(&centroid(&samples, b))
+        };
+        let expected = [
+            (Linkage::Single,   brute_force_heights(samples.len(), single_linkage(distance))),
+            (Linkage::Complete, brute_force_heights(samples.len(), complete_linkage(distance))),
+            (Linkage::Average,  brute_force_heights(samples.len(), average_linkage(distance))),
+            (Linkage::Centroid, brute_force_heights(samples.len(), |a, b| centroid(&samples, a).distance(&centroid(&samples, b)))),
+            (Linkage::Ward,     brute_force_heights(samples.len(), ward)),
+        ];
+        for (linkage, expected) in expected {
+            // Merges are compared in the order they are found, since centroid linkage is not
+            // monotone.
+            let heights = merges(&distance_matrix, linkage, false).into_iter().map(|(_, _, height)| height).collect::<Vec<_>>();
+            assert_heights_close(&heights, &expected);
+        }
+    }
+
+    #[test]
+    fn test_dendrogram() {
+        let samples = random_samples(50);
+        let expected = slink(&samples, |a, b| a.distance(b));
+        let dendrogram = lance_williams(&samples, |a, b| a.distance(b), Linkage::Single);
+        for cluster_count in 1..=samples.len() {
+            assert_eq!(dendrogram.section_with_cluster_count(cluster_count).labels, expected.section_with_cluster_count(cluster_count).labels);
+        }
+
+        for linkage in [Linkage::Complete, Linkage::Average] {
+            let dendrogram = lance_williams(&samples, |a, b| a.distance(b), linkage);
+            for cluster_count in [1, 2, 5, 10] {
+                let distance = |i : usize, j : usize| samples[i].distance(&samples[j]);
+                let clusters = match linkage {
+                    Linkage::Complete => naive(samples.len(), cluster_count, complete_linkage(distance)),
+                    _                 => naive(samples.len(), cluster_count, average_linkage(distance)),
+                };
+                let labels = dendrogram.section_with_cluster_count(cluster_count).labels;
+                for cluster in clusters {
+                    assert!(cluster.iter().all(|&index| labels[index] == labels[cluster[0]]));
+                }
+            }
+        }
+
+        // Degenerate inputs and inversions do not break anything.
+        for linkage in LINKAGES {
+            assert!(lance_williams(&samples[..0], |a, b| a.distance(b), linkage).is_empty());
+            assert_eq!(lance_williams(&samples[..1], |a, b| a.distance(b), linkage).len(), 1);
+            let dendrogram = lance_williams(&samples, |a, b| a.distance(b), linkage);
+            assert_eq!(dendrogram.section_with_cluster_count(3).labels.iter().max(), Some(&2));
+        }
+    }
+}
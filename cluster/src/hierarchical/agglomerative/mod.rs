@@ -2,5 +2,10 @@ pub mod dendrogram;
 pub mod naive;
 pub mod clink;
 pub mod slink;
+pub mod lance_williams;
+pub mod ward;
 
 pub(crate) mod disjoint_set;
+
+#[cfg(test)]
+pub(crate) mod test_util;
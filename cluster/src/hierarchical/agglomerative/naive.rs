@@ -1,7 +1,11 @@
 //! Naive implemetation of hierarchical agglomerative clustering.
 //!
 //! This implementation focus on extensiblility rather than optimal performance. For more efficient
-//! implementation of specific linkage criterion, see [super::slink] and [super::clink].
+//! implementation of specific linkage criterion, see [super::slink], [super::clink] and
+//! [super::lance_williams].
+
+use crate::distance_matrix::DistanceMatrix;
+use crate::metric::Metric;
 
 pub fn naive<D>(sample_count : usize, cluster_count : usize, mut distance: D) -> Vec<Vec<usize>>
 where
@@ -27,12 +31,40 @@ where
     clusters
 }
 
+/// [naive] on precomputed distances.
+///
+/// Linkage is one of [single_linkage], [complete_linkage] and [average_linkage], or anything else
+/// that turns distances between samples into distances between clusters.
+///
+/// **Example**: `naive_precomputed(&distance_matrix, 3, average_linkage)`
+#[allow(clippy::needless_lifetimes)] // Elision would make linkage higher-ranked over the lifetime.
+pub fn naive_precomputed<'a, L, C>(distance_matrix : &'a DistanceMatrix, cluster_count : usize, linkage : L) -> Vec<Vec<usize>>
+where
+    L: FnOnce(Box<dyn Fn(usize, usize) -> f64 + 'a>) -> C,
+    C: FnMut(&[usize], &[usize]) -> f64
+{
+    naive(distance_matrix.sample_count(), cluster_count, linkage(Box::new(distance_matrix.dissimilarity())))
+}
+
+/// [naive] on samples with distance given by metric.
+///
+/// Linkage is as in [naive_precomputed].
+#[allow(clippy::needless_lifetimes)] // Elision would make linkage higher-ranked over the lifetime.
+pub fn naive_with_metric<'a, P, M, L, C>(samples : &'a [P], cluster_count : usize, metric : &'a M, linkage : L) -> Vec<Vec<usize>>
+where
+    M: Metric<P>,
+    L: FnOnce(Box<dyn Fn(usize, usize) -> f64 + 'a>) -> C,
+    C: FnMut(&[usize], &[usize]) -> f64
+{
+    naive(samples.len(), cluster_count, linkage(Box::new(|i, j| metric.distance(&samples[i], &samples[j]))))
+}
+
 pub fn single_linkage<D>(mut distance : D) -> impl FnMut(&[usize], &[usize]) -> f64
 where
     D: FnMut(usize, usize) -> f64
 {
     move |cluster1, cluster2| {
-        itertools::Itertools::cartesian_product(cluster1.into_iter(), cluster2.into_iter())
+        itertools::Itertools::cartesian_product(cluster1.iter(), cluster2.iter())
             .map(|(&item1, &item2)| distance(item1, item2))
             .min_by(|a, b| f64::partial_cmp(a, b).unwrap())
             .unwrap()
@@ -44,7 +76,7 @@ where
     D: FnMut(usize, usize) -> f64
 {
     move |cluster1, cluster2| {
-        itertools::Itertools::cartesian_product(cluster1.into_iter(), cluster2.into_iter())
+        itertools::Itertools::cartesian_product(cluster1.iter(), cluster2.iter())
             .map(|(&item1, &item2)| distance(item1, item2))
             .max_by(|a, b| f64::partial_cmp(a, b).unwrap())
             .unwrap()
@@ -56,7 +88,7 @@ where
     D: FnMut(usize, usize) -> f64
 {
     move |cluster1, cluster2| {
-        let (total, count) = itertools::Itertools::cartesian_product(cluster1.into_iter(), cluster2.into_iter())
+        let (total, count) = itertools::Itertools::cartesian_product(cluster1.iter(), cluster2.iter())
             .map(|(&item1, &item2)| distance(item1, item2))
             .fold((0.0, 0), |(acc_total, acc_count), distance| (acc_total + distance, acc_count + 1));
 
@@ -64,3 +96,35 @@ where
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::slink::*;
+    use crate::metric::Manhattan;
+    use crate::test_util::levenshtein;
+
+    use math::prelude::*;
+
+    #[test]
+    fn test_precomputed() {
+        let samples = ["kitten", "sitten", "mitten", "banana", "bandana", "cabana"];
+        let distance_matrix = DistanceMatrix::from_samples(&samples, |a, b| levenshtein(a, b));
+        let sorted = |mut clusters : Vec<Vec<usize>>| {
+            clusters.iter_mut().for_each(|cluster| cluster.sort());
+            clusters.sort();
+            clusters
+        };
+        let expected = vec![vec![0, 1, 2], vec![3, 4, 5]];
+        assert_eq!(sorted(naive_precomputed(&distance_matrix, 2, single_linkage)), expected);
+        assert_eq!(sorted(naive_precomputed(&distance_matrix, 2, complete_linkage)), expected);
+        assert_eq!(sorted(naive_precomputed(&distance_matrix, 2, average_linkage)), expected);
+
+        let points = [0.0, 1.0, 1.5, 7.0, 8.0, 10.0].map(|x| Vector::from_array([x]));
+        assert_eq!(sorted(naive_with_metric(&points, 2, &Manhattan, complete_linkage)), expected);
+
+        let expected = slink(&samples, |a, b| levenshtein(a, b)).section_with_cluster_count(2);
+        let actual = slink_precomputed(&distance_matrix).section_with_cluster_count(2);
+        assert_eq!(actual.labels, expected.labels);
+    }
+}
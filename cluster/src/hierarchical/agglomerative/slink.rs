@@ -1,4 +1,6 @@
 use super::dendrogram::Dendrogram;
+use crate::distance_matrix::DistanceMatrix;
+use crate::metric::Metric;
 
 /// Implementation of SLINK algorithm for single-linkage clustering.
 ///
@@ -53,3 +55,14 @@ where
     )
 }
 
+
+/// [slink] on precomputed distances.
+pub fn slink_precomputed(distance_matrix : &DistanceMatrix) -> Dendrogram {
+    let samples = (0..distance_matrix.sample_count()).collect::<Vec<_>>();
+    slink(&samples, |&i, &j| distance_matrix.get(i, j))
+}
+
+/// [slink] with dissimilarity given by metric.
+pub fn slink_with_metric<P, M: Metric<P>>(samples : &[P], metric : &M) -> Dendrogram {
+    slink(samples, |a, b| metric.distance(a, b))
+}
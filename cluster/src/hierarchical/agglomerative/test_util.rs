@@ -0,0 +1,16 @@
+//! Fixtures shared by tests of agglomerative clustering.
+
+use math::prelude::*;
+use rand::prelude::*;
+
+/// Relabel clusters in order of first appearance, so that equal partitions have equal labels.
+pub(crate) fn canonical(labels : Vec<usize>) -> Vec<usize> {
+    let mut mapping = std::collections::HashMap::new();
+    labels.into_iter().map(|label| { let next = mapping.len(); *mapping.entry(label).or_insert(next) }).collect()
+}
+
+/// Samples drawn uniformly from the unit square.
+pub(crate) fn random_samples(sample_count : usize) -> Vec<Vector<2>> {
+    let mut rng = thread_rng();
+    (0..sample_count).map(|_| Vector::from_array([rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)])).collect()
+}
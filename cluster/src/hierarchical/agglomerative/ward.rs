@@ -0,0 +1,90 @@
+//! Ward's minimum variance agglomerative clustering on points.
+//!
+//! This gives the same dendrogram as [super::lance_williams] with [super::lance_williams::Linkage::Ward],
+//! but clusters are represented by their centroids instead of a distance matrix, so that only
+//! O(n) memory is needed.
+
+use super::dendrogram::Dendrogram;
+use super::lance_williams::nn_chain;
+
+use math::prelude::*;
+
+/// Ward's minimum variance clustering.
+///
+/// Merge heights are sqrt(2 * size1 * size2 / (size1 + size2)) times the distance between
+/// centroids of the two clusters, which is the same as in SciPy. Squared merge height is twice
+/// the increase in within-cluster sum of squared errors caused by the merge.
+pub fn ward<P: Point>(samples : &[P]) -> Dendrogram {
+    ward_weighted(samples, &vec![1.0; samples.len()])
+}
+
+/// Ward's minimum variance clustering on weighted samples.
+///
+/// A sample with weight w behaves as w coincident samples, apart from the merges between them.
+/// This allows clustering a histogram of samples instead of the samples themselves.
+pub fn ward_weighted<P: Point>(samples : &[P], weights : &[f64]) -> Dendrogram {
+    assert_eq!(samples.len(), weights.len());
+    assert!(weights.iter().all(|&weight| weight > 0.0));
+
+    // Centroids and weights of clusters.
+    let mut clusters = (samples.to_vec(), weights.to_vec());
+
+    // Squared merge height, which is proportional to increase in sum of squared errors.
+    let cost = |(centroids, weights) : &(Vec<P>, Vec<f64>), i : usize, j : usize| 2.0 * weights[i] * weights[j] / (weights[i] + weights[j]) * centroids[i].squared_distance(&centroids[j]);
+
+    // Merge cluster i into cluster j.
+    let merge = |(centroids, weights) : &mut (Vec<P>, Vec<f64>), i : usize, j : usize| {
+        let weight = weights[i] + weights[j];
+        centroids[j] = (centroids[i].clone() * weights[i] + centroids[j].clone() * weights[j]) / weight;
+        weights[j] = weight;
+    };
+
+    let mut merges = nn_chain(samples.len(), &mut clusters, cost, merge)
+        .into_iter()
+        .map(|(i, j, cost)| (i, j, cost.sqrt()))
+        .collect::<Vec<_>>();
+    merges.sort_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b));
+    Dendrogram::from_merges(samples.len(), merges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::lance_williams::*;
+    use super::super::test_util::canonical;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn test_ward() {
+        let mut rng = thread_rng();
+        let samples = (0..60).map(|_| Vector::from_array([rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)])).collect::<Vec<_>>();
+        let expected = lance_williams(&samples, |a, b| a.distance(b), Linkage::Ward);
+        let dendrogram = ward(&samples);
+        for cluster_count in 1..=samples.len() {
+            assert_eq!(canonical(dendrogram.section_with_cluster_count(cluster_count).labels), canonical(expected.section_with_cluster_count(cluster_count).labels));
+        }
+
+        assert!(ward::<Vector<3>>(&[]).is_empty());
+        assert_eq!(ward(&samples[..1]).len(), 1);
+    }
+
+    #[test]
+    fn test_ward_weighted() {
+        let mut rng = thread_rng();
+        let samples = (0..30).map(|_| DVector::from_fn(2, |_| rng.gen_range(0.0..1.0))).collect::<Vec<_>>();
+        let weights = (0..30).map(|_| rng.gen_range(1..4)).collect::<Vec<usize>>();
+
+        // Weighted samples behave as repeated samples once the repeats are merged.
+        let repeated = std::iter::zip(&samples, &weights).flat_map(|(sample, &weight)| std::iter::repeat_n(sample.clone(), weight)).collect::<Vec<_>>();
+        let firsts = weights.iter().scan(0, |first, &weight| { let result = *first; *first += weight; Some(result) }).collect::<Vec<_>>();
+
+        let weighted = ward_weighted(&samples, &weights.iter().map(|&weight| weight as f64).collect::<Vec<_>>());
+        let expected = ward(&repeated);
+        for cluster_count in 1..=samples.len() {
+            let expected = expected.section_with_cluster_count(cluster_count).labels;
+            let expected = firsts.iter().map(|&first| expected[first]).collect::<Vec<_>>();
+            assert_eq!(canonical(weighted.section_with_cluster_count(cluster_count).labels), canonical(expected));
+        }
+    }
+}
@@ -2,9 +2,16 @@
 #![allow(clippy::map_flatten)]
 #![allow(clippy::manual_memcpy)]
 #![allow(clippy::type_complexity)]
+#![allow(clippy::collapsible_if)]
 
 //! Implementation of various clustering algorithms.
 
 pub mod expectation_maximization;
 pub mod hierarchical;
 pub mod misc;
+pub mod spatial;
+pub mod distance_matrix;
+pub mod metric;
+
+#[cfg(test)]
+pub(crate) mod test_util;
@@ -0,0 +1,166 @@
+//! Distance metrics between points.
+//!
+//! Algorithms that accept a [Metric] can switch between notions of distance without any change to
+//! the algorithm itself. Not every metric here satisfies the triangle inequality. In particular,
+//! [SquaredEuclidean] and [Cosine] do not, which matters for algorithms that rely on it for
+//! pruning, but not for the ones in this crate that accept a [Metric].
+
+use math::prelude::*;
+use math::lu::SingularMatrixError;
+
+/// Distance between two points.
+///
+/// Metrics are shared across threads by algorithms that evaluate distances in parallel.
+pub trait Metric<P>: Sync {
+    fn distance(&self, a : &P, b : &P) -> f64;
+}
+
+/// Euclidean distance, i.e. L2 distance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Euclidean;
+
+impl<P: Point> Metric<P> for Euclidean {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        a.squared_distance(b).sqrt()
+    }
+}
+
+/// Squared Euclidean distance, which is what K-Means minimizes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SquaredEuclidean;
+
+impl<P: Point> Metric<P> for SquaredEuclidean {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        a.squared_distance(b)
+    }
+}
+
+/// Manhattan distance, i.e. L1 distance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Manhattan;
+
+impl<P: Point> Metric<P> for Manhattan {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        assert_eq!(a.dimension(), b.dimension());
+        std::iter::zip(a.as_slice(), b.as_slice()).map(|(x, y)| (x - y).abs()).sum()
+    }
+}
+
+/// Chebyshev distance, i.e. L-infinity distance.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chebyshev;
+
+impl<P: Point> Metric<P> for Chebyshev {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        assert_eq!(a.dimension(), b.dimension());
+        std::iter::zip(a.as_slice(), b.as_slice()).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max)
+    }
+}
+
+/// Minkowski distance, i.e. Lp distance.
+///
+/// This is [Manhattan] for p = 1 and [Euclidean] for p = 2, and tends to [Chebyshev] as p grows.
+#[derive(Debug, Clone, Copy)]
+pub struct Minkowski {
+    p : f64,
+}
+
+impl Minkowski {
+    /// Panic if p < 1, for which this is not a metric.
+    pub fn new(p : f64) -> Self {
+        assert!(p >= 1.0, "minkowski distance requires p >= 1");
+        Self { p }
+    }
+
+    pub fn p(&self) -> f64 {
+        self.p
+    }
+}
+
+impl<P: Point> Metric<P> for Minkowski {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        assert_eq!(a.dimension(), b.dimension());
+        std::iter::zip(a.as_slice(), b.as_slice()).map(|(x, y)| (x - y).abs().powf(self.p)).sum::<f64>().powf(self.p.recip())
+    }
+}
+
+/// Cosine distance, i.e. one minus cosine similarity, ranging from 0 to 2.
+///
+/// A zero vector is at distance 0 from another zero vector and at distance 1 from anything else.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cosine;
+
+impl<P: Point> Metric<P> for Cosine {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        let norm = (a.dot(a) * b.dot(b)).sqrt();
+        if norm == 0.0 {
+            return if a.dot(a) == b.dot(b) { 0.0 } else { 1.0 };
+        }
+        (1.0 - a.dot(b) / norm).clamp(0.0, 2.0)
+    }
+}
+
+/// Mahalanobis distance with respect to a covariance matrix.
+#[derive(Debug, Clone)]
+pub struct Mahalanobis<M> {
+    pub inverse_covariance : M,
+}
+
+impl<M: PointMatrix> Mahalanobis<M> {
+    /// Panic if covariance is singular. See [Self::try_new] for a fallible alternative.
+    pub fn new(covariance : &M) -> Self {
+        Self::try_new(covariance).expect("covariance is singular")
+    }
+
+    /// Return [SingularMatrixError] if covariance is singular, e.g. if it is the sample covariance
+    /// of samples lying in a lower dimensional subspace.
+    pub fn try_new(covariance : &M) -> Result<Self, SingularMatrixError> {
+        Ok(Self { inverse_covariance : covariance.try_inverse()? })
+    }
+}
+
+impl<P: Point> Metric<P> for Mahalanobis<P::Matrix> {
+    fn distance(&self, a : &P, b : &P) -> f64 {
+        let difference = a.clone() - b.clone();
+        difference.dot(&self.inverse_covariance.transform(&difference)).max(0.0).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a : f64, b : f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_metrics() {
+        let a = Vector::from_array([1.0, 2.0]);
+        let b = Vector::from_array([4.0, -2.0]);
+        assert_close(Euclidean.distance(&a, &b), 5.0);
+        assert_close(SquaredEuclidean.distance(&a, &b), 25.0);
+        assert_close(Manhattan.distance(&a, &b), 7.0);
+        assert_close(Chebyshev.distance(&a, &b), 4.0);
+        assert_close(Minkowski::new(1.0).distance(&a, &b), 7.0);
+        assert_close(Minkowski::new(2.0).distance(&a, &b), 5.0);
+        assert_close(Minkowski::new(3.0).distance(&a, &b), 91.0f64.cbrt());
+
+        let x = DVector::from_fn(2, |i| [1.0, 0.0][i]);
+        let y = DVector::from_fn(2, |i| [0.0, 3.0][i]);
+        let zero = DVector::zero(2);
+        assert_close(Cosine.distance(&x, &y), 1.0);
+        assert_close(Cosine.distance(&x, &(x.clone() * 2.0)), 0.0);
+        assert_close(Cosine.distance(&x, &(x.clone() * -1.0)), 2.0);
+        assert_close(Cosine.distance(&zero, &zero), 0.0);
+        assert_close(Cosine.distance(&zero, &x), 1.0);
+
+        // Identity covariance gives Euclidean distance, and scaling a dimension of the covariance
+        // by s^2 scales distances along it by 1/s.
+        assert_close(Mahalanobis::new(&Matrix::<2>::one()).distance(&a, &b), 5.0);
+        let covariance = Matrix::<2>::from_fn(|(j, i)| if (j, i) == (0, 0) { 9.0 } else if j == i { 16.0 } else { 0.0 });
+        assert_close(Mahalanobis::new(&covariance).distance(&a, &b), 2.0f64.sqrt());
+        assert!(Mahalanobis::try_new(&Matrix::<2>::from_array([[1.0, 2.0], [2.0, 4.0]])).is_err());
+        assert_eq!(Minkowski::new(3.0).p(), 3.0);
+    }
+}
@@ -1,4 +1,7 @@
 use itertools::Itertools;
+use rayon::prelude::*;
+
+use crate::distance_matrix::DistanceMatrix;
 
 fn lerp(a : f64, low : f64, high : f64) -> f64 {
     (1.0 - a) * low + a * high
@@ -10,6 +13,19 @@ pub struct AffinityPropagation {
     similarities : Vec<f64>,
     responsiblities : Vec<f64>,
     availabilities : Vec<f64>,
+
+    /// Execute updates in parallel on the global thread pool.
+    ///
+    /// Results are identical regardless of this setting.
+    pub parallel : bool,
+}
+
+/// Positive responsibilities in a column, which are all that availabilities depend on.
+struct ColumnSums {
+    /// Sum of positive responsibilities r(i', k) for i' != k, in increasing order of i'.
+    total : f64,
+    /// (i, sum excluding r(i, k)) for each i != k with r(i, k) > 0.
+    exclusions : Vec<(usize, f64)>,
 }
 
 impl AffinityPropagation {
@@ -29,46 +45,115 @@ impl AffinityPropagation {
         let responsiblities = vec![0.0; sample_count * sample_count];
         let availabilities  = vec![0.0; sample_count * sample_count];
 
-        Self { sample_count, similarities, responsiblities, availabilities }
+        Self { sample_count, similarities, responsiblities, availabilities, parallel : false }
     }
 
+    /// Construct from precomputed distances, with similarity being negated distance.
+    ///
+    /// The negated median distance is a typical choice of preference, analogous to
+    /// [median_preference].
+    pub fn from_distance_matrix(distance_matrix : &DistanceMatrix, preference : f64) -> Self {
+        let samples = (0..distance_matrix.sample_count()).collect::<Vec<_>>();
+        Self::new(&samples, |&i, &k| -distance_matrix.get(i, k), preference)
+    }
+
+    /// Update responsibilities and then availabilities in O(n^2).
+    ///
+    /// The max over k' != k in the responsibility update is taken from the top two values of each
+    /// row. The sum over i' not in {i, k} in the availability update is evaluated in the same order
+    /// as a naive O(n^3) implementation so that results are bit-for-bit identical to it. Zero terms
+    /// do not change the sum, so it only needs to be recomputed for samples whose own
+    /// responsibility in the column is positive. This takes O(m^2) for m positive
+    /// responsibilities in a column, which are few in practice.
     pub fn update(&mut self, damping : f64) {
+        let sample_count = self.sample_count;
+        if sample_count == 0 {
+            return;
+        }
+
         // Responsibilities update
-        for i in 0..self.sample_count {
-            for k in 0..self.sample_count {
-                let mut new_responsibility = 0.0;
+        let update_responsibility_row = |(i, responsibility_row) : (usize, &mut [f64])| {
+            let availability_row = &self.availabilities[i * sample_count..(i + 1) * sample_count];
+            let similarity_row = &self.similarities[i * sample_count..(i + 1) * sample_count];
 
-                new_responsibility += self.similarities[i * self.sample_count + k];
-                new_responsibility -= (0..self.sample_count)
-                    .filter(|&kp| kp != k)
-                    .map(|kp| self.availabilities[i * self.sample_count + kp] + self.similarities[i * self.sample_count + kp])
-                    .max_by(|a, b| f64::partial_cmp(a, b).unwrap())
-                    .unwrap();
+            // Ties are resolved in favor of the last index, as in Iterator::max_by.
+            let mut first = (sample_count, f64::NEG_INFINITY);
+            let mut second = f64::NEG_INFINITY;
+            for kp in 0..sample_count {
+                let value = availability_row[kp] + similarity_row[kp];
+                if value >= first.1 {
+                    second = first.1;
+                    first = (kp, value);
+                } else if value >= second {
+                    second = value;
+                }
+            }
 
-                self.responsiblities[i * self.sample_count + k] = lerp(damping, new_responsibility, self.responsiblities[i * self.sample_count + k]);
+            for k in 0..sample_count {
+                let max = if k == first.0 { second } else { first.1 };
+                let new_responsibility = similarity_row[k] - max;
+                responsibility_row[k] = lerp(damping, new_responsibility, responsibility_row[k]);
             }
+        };
+        if self.parallel {
+            self.responsiblities.par_chunks_mut(sample_count).enumerate().for_each(update_responsibility_row);
+        } else {
+            self.responsiblities.chunks_mut(sample_count).enumerate().for_each(update_responsibility_row);
         }
 
         // Availabilities update
-        for i in 0..self.sample_count {
-            for k in 0..self.sample_count {
-                let mut new_availability = 0.0;
+        let column_sums = |k : usize| {
+            let positives = (0..sample_count)
+                .filter(|&ip| ip != k && self.responsiblities[ip * sample_count + k] > 0.0)
+                .map(|ip| (ip, self.responsiblities[ip * sample_count + k]))
+                .collect::<Vec<_>>();
 
-                for ip in 0..self.sample_count {
-                    if ip != i && ip != k {
-                        new_availability += self.responsiblities[ip * self.sample_count + k].max(0.0);
-                    }
+            let mut total = 0.0;
+            let mut exclusions = Vec::with_capacity(positives.len());
+            for (index, &(i, value)) in positives.iter().enumerate() {
+                let mut exclusion = total;
+                for &(_, value) in &positives[index + 1..] {
+                    exclusion += value;
                 }
+                exclusions.push((i, exclusion));
+                total += value;
+            }
+            ColumnSums { total, exclusions }
+        };
+        let column_sums = if self.parallel {
+            (0..sample_count).into_par_iter().map(column_sums).collect::<Vec<_>>()
+        } else {
+            (0..sample_count).map(column_sums).collect::<Vec<_>>()
+        };
 
-                if i != k {
-                    new_availability += self.responsiblities[k * self.sample_count + k];
-                    new_availability = new_availability.min(0.0);
+        let self_responsibilities = (0..sample_count).map(|k| self.responsiblities[k * sample_count + k]).collect::<Vec<_>>();
+        let update_availability_row = |(i, availability_row) : (usize, &mut [f64])| {
+            let responsibility_row = &self.responsiblities[i * sample_count..(i + 1) * sample_count];
+            for k in 0..sample_count {
+                if i != k && responsibility_row[k] > 0.0 {
+                    continue; // Handled below
                 }
 
-                self.availabilities[i * self.sample_count + k] = lerp(damping, new_availability, self.availabilities[i * self.sample_count + k]);
+                let new_availability = if i != k {
+                    (column_sums[k].total + self_responsibilities[k]).min(0.0)
+                } else {
+                    column_sums[k].total
+                };
+                availability_row[k] = lerp(damping, new_availability, availability_row[k]);
             }
+        };
+        if self.parallel {
+            self.availabilities.par_chunks_mut(sample_count).enumerate().for_each(update_availability_row);
+        } else {
+            self.availabilities.chunks_mut(sample_count).enumerate().for_each(update_availability_row);
         }
 
+        for (k, column_sums) in column_sums.iter().enumerate() {
+            for &(i, exclusion) in &column_sums.exclusions {
+                let new_availability = (exclusion + self_responsibilities[k]).min(0.0);
+                self.availabilities[i * sample_count + k] = lerp(damping, new_availability, self.availabilities[i * sample_count + k]);
+            }
+        }
     }
 
     pub fn exemplers(&self) -> Vec<usize> {
@@ -91,24 +176,222 @@ impl AffinityPropagation {
     }
 }
 
+/// Criteria for terminating [affinity_propagation].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConvergenceCriteria {
+    /// Give up after this many iterations.
+    pub max_iterations : usize,
+    /// Converge once the set of exemplers is non-empty and unchanged for this many consecutive
+    /// iterations.
+    pub convergence_iterations : usize,
+}
+
+impl Default for ConvergenceCriteria {
+    fn default() -> Self {
+        Self { max_iterations : 200, convergence_iterations : 15, }
+    }
+}
+
+impl ConvergenceCriteria {
+    /// Iterate step, which returns exemplers, until convergence.
+    ///
+    /// Return converged exemplers and number of iterations.
+    pub(crate) fn iterate<F>(self, mut step : F) -> Result<(Vec<usize>, usize), DidNotConverge>
+    where
+        F: FnMut() -> Vec<usize>,
+    {
+        let mut exemplers = Vec::new();
+        let mut stable_iterations = 0;
+        for iterations in 1..=self.max_iterations {
+            let new_exemplers = step();
+            stable_iterations = if !new_exemplers.is_empty() && new_exemplers == exemplers { stable_iterations + 1 } else { 1 };
+            exemplers = new_exemplers;
+            if !exemplers.is_empty() && stable_iterations >= self.convergence_iterations {
+                return Ok((exemplers, iterations));
+            }
+        }
+        Err(DidNotConverge { iterations : self.max_iterations })
+    }
+}
+
+/// Error returned if exemplers did not settle within [ConvergenceCriteria::max_iterations].
+///
+/// This typically happens if damping is too low, so that exemplers oscillate, or if the
+/// preference is so low that no exempler emerges.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DidNotConverge {
+    pub iterations : usize,
+}
+
+impl std::fmt::Display for DidNotConverge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "affinity propagation did not converge in {} iterations", self.iterations)
+    }
+}
+
+impl std::error::Error for DidNotConverge {}
+
+/// Median of a non-empty list of values.
+pub(crate) fn median(mut values : Vec<f64>) -> f64 {
+    assert!(!values.is_empty());
+    let count = values.len();
+    let (lower, &mut upper, _) = values.select_nth_unstable_by(count / 2, f64::total_cmp);
+    if count.is_multiple_of(2) {
+        0.5 * (lower.iter().copied().max_by(f64::total_cmp).unwrap() + upper)
+    } else {
+        upper
+    }
+}
+
+/// Median of similarities between distinct samples, or None if there are fewer than 2 samples.
+///
+/// This is the usual choice of preference, which gives a moderate number of clusters.
+pub fn median_preference<T, S>(samples : &[T], similarity : S) -> Option<f64>
+where
+    S: Fn(&T, &T) -> f64,
+{
+    let similarities = pairwise_similarities(samples, similarity);
+    (!similarities.is_empty()).then(|| median(similarities))
+}
+
+/// Minimum of similarities between distinct samples, or None if there are fewer than 2 samples.
+///
+/// This gives a small number of clusters.
+pub fn minimum_preference<T, S>(samples : &[T], similarity : S) -> Option<f64>
+where
+    S: Fn(&T, &T) -> f64,
+{
+    pairwise_similarities(samples, similarity).into_iter().min_by(f64::total_cmp)
+}
+
+fn pairwise_similarities<T, S>(samples : &[T], similarity : S) -> Vec<f64>
+where
+    S: Fn(&T, &T) -> f64,
+{
+    let mut similarities = Vec::with_capacity(samples.len() * samples.len().saturating_sub(1));
+    for i in 0..samples.len() {
+        for k in 0..samples.len() {
+            if i != k {
+                similarities.push(similarity(&samples[i], &samples[k]));
+            }
+        }
+    }
+    similarities
+}
+
 /// Implementation of affinity propagation clustering.
 ///
-/// The number of output cluster is not fixed.
-/// Return exemplers and labels.
-pub fn affinity_propagation<T, S>(samples : &[T], similarity : S, preference : f64, damping : f64) -> (Vec<usize>, Vec<usize>)
+/// The number of output cluster is not fixed. See [median_preference] and [minimum_preference]
+/// for choosing preference.
+/// Return exemplers and labels, or [DidNotConverge] if criteria.max_iterations is reached first.
+pub fn affinity_propagation<T, S>(samples : &[T], similarity : S, preference : f64, damping : f64, criteria : ConvergenceCriteria) -> Result<(Vec<usize>, Vec<usize>), DidNotConverge>
 where
     T: Copy,
     S: Fn(&T, &T) -> f64,
 {
     let mut ap = AffinityPropagation::new(samples, similarity, preference);
-    let mut exemplers = { ap.update(damping); ap.exemplers() };
-    loop {
-        let new_exemplers = { ap.update(damping); ap.exemplers() };
-        if !exemplers.is_empty() && exemplers == new_exemplers {
-            let labels = ap.labels(&exemplers);
-            break (exemplers, labels)
+    let (exemplers, _) = criteria.iterate(|| { ap.update(damping); ap.exemplers() })?;
+    let labels = ap.labels(&exemplers);
+    Ok((exemplers, labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+
+    /// Straightforward O(n^3) update.
+    fn update_naive(ap : &mut AffinityPropagation, damping : f64) {
+        for i in 0..ap.sample_count {
+            for k in 0..ap.sample_count {
+                let mut new_responsibility = 0.0;
+
+                new_responsibility += ap.similarities[i * ap.sample_count + k];
+                new_responsibility -= (0..ap.sample_count)
+                    .filter(|&kp| kp != k)
+                    .map(|kp| ap.availabilities[i * ap.sample_count + kp] + ap.similarities[i * ap.sample_count + kp])
+                    .max_by(|a, b| f64::partial_cmp(a, b).unwrap())
+                    .unwrap();
+
+                ap.responsiblities[i * ap.sample_count + k] = lerp(damping, new_responsibility, ap.responsiblities[i * ap.sample_count + k]);
+            }
+        }
+
+        for i in 0..ap.sample_count {
+            for k in 0..ap.sample_count {
+                let mut new_availability = 0.0;
+
+                for ip in 0..ap.sample_count {
+                    if ip != i && ip != k {
+                        new_availability += ap.responsiblities[ip * ap.sample_count + k].max(0.0);
+                    }
+                }
+
+                if i != k {
+                    new_availability += ap.responsiblities[k * ap.sample_count + k];
+                    new_availability = new_availability.min(0.0);
+                }
+
+                ap.availabilities[i * ap.sample_count + k] = lerp(damping, new_availability, ap.availabilities[i * ap.sample_count + k]);
+            }
         }
-        exemplers = new_exemplers;
     }
-}
 
+    #[test]
+    fn test_update() {
+        let mut rng = thread_rng();
+        let samples = (0..60).map(|_| [rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)]).collect::<Vec<_>>();
+        let similarity = |a : &[f64; 2], b : &[f64; 2]| -((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2));
+
+        let mut expected = AffinityPropagation::new(&samples, similarity, -0.5);
+        let mut serial = AffinityPropagation::new(&samples, similarity, -0.5);
+        let mut parallel = AffinityPropagation { parallel : true, ..AffinityPropagation::new(&samples, similarity, -0.5) };
+        for _ in 0..50 {
+            update_naive(&mut expected, 0.5);
+            serial.update(0.5);
+            parallel.update(0.5);
+            for ap in [&serial, &parallel] {
+                assert!(std::iter::zip(&ap.responsiblities, &expected.responsiblities).all(|(a, b)| a.to_bits() == b.to_bits()));
+                assert!(std::iter::zip(&ap.availabilities, &expected.availabilities).all(|(a, b)| a.to_bits() == b.to_bits()));
+            }
+        }
+        assert_eq!(serial.exemplers(), expected.exemplers());
+    }
+
+    #[test]
+    fn test_convergence() {
+        let samples = [0.0, 0.1, 0.2, 5.0, 5.1, 5.2, 10.0, 10.1, 10.2];
+        let similarity = |a : &f64, b : &f64| -(a - b) * (a - b);
+
+        let preference = median_preference(&samples, similarity).unwrap();
+        let (exemplers, labels) = affinity_propagation(&samples, similarity, preference, 0.5, ConvergenceCriteria::default()).unwrap();
+        assert_eq!(exemplers, vec![1, 4, 7]);
+        assert_eq!(labels, vec![0, 0, 0, 1, 1, 1, 2, 2, 2]);
+
+        // Cannot converge within a window longer than allowed iterations.
+        let criteria = ConvergenceCriteria { max_iterations : 10, convergence_iterations : 11 };
+        assert_eq!(affinity_propagation(&samples, similarity, preference, 0.5, criteria), Err(DidNotConverge { iterations : 10 }));
+    }
+
+    #[test]
+    fn test_preference() {
+        let samples = [0.0, 1.0, 3.0];
+        let similarity = |a : &f64, b : &f64| -(a - b).abs();
+        assert_eq!(median_preference(&samples, similarity), Some(-2.0));
+        assert_eq!(minimum_preference(&samples, similarity), Some(-3.0));
+        assert_eq!(median_preference(&samples[..1], similarity), None);
+        assert_eq!(minimum_preference(&samples[..1], similarity), None);
+        assert_eq!(median_preference(&samples[..0], similarity), None);
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+    }
+
+    #[test]
+    fn test_distance_matrix() {
+        let samples = [0.0, 1.0, 3.0, 10.0];
+        let distance = |a : &f64, b : &f64| (a - b).abs();
+        let distance_matrix = DistanceMatrix::from_samples(&samples, distance);
+        let ap = AffinityPropagation::from_distance_matrix(&distance_matrix, -2.0);
+        assert_eq!(ap.similarities, AffinityPropagation::new(&samples, |a, b| -distance(a, b), -2.0).similarities);
+    }
+}
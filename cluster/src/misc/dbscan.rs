@@ -1,58 +1,198 @@
+use crate::spatial::SpatialIndex;
+use crate::spatial::kd_tree::KdTree;
+use crate::distance_matrix::DistanceMatrix;
+use crate::metric::Metric;
+
 use math::prelude::*;
 
-fn neighbours<const N: usize>(samples : &[Vector<N>], epsilon : f64, index : usize) -> Vec<usize> {
-    let mut result = Vec::new();
-    for other_index in 0..samples.len() {
-        if other_index != index {
-            if (samples[other_index] - samples[index]).squared_length() < epsilon*epsilon {
-                result.push(other_index);
-            }
+fn neighbours<P: Point, I: SpatialIndex<P>>(samples : &[P], index : &I, epsilon : f64, sample_index : usize) -> Vec<usize> {
+    let mut result = index.within_radius(&samples[sample_index], epsilon);
+    result.retain(|&other_index| other_index != sample_index);
+    result
+}
+
+/// Classification of a sample by DBSCAN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleLabel {
+    /// Sample with at least min_pts samples (including itself) within epsilon.
+    Core(usize),
+    /// Sample that is not a core sample but is within epsilon of a core sample.
+    ///
+    /// A border sample within epsilon of core samples from multiple clusters is assigned to the
+    /// cluster that is discovered first.
+    Border(usize),
+    /// Sample that is neither a core sample nor a border sample.
+    Noise,
+}
+
+impl SampleLabel {
+    /// Cluster the sample belongs to, or None if it is noise.
+    pub fn cluster(self) -> Option<usize> {
+        match self {
+            SampleLabel::Core(cluster_index) | SampleLabel::Border(cluster_index) => Some(cluster_index),
+            SampleLabel::Noise => None,
         }
     }
-    result
+}
+
+/// Result of DBSCAN.
+///
+/// Clusters are numbered contiguously from 0 in order of discovery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbscanResult {
+    pub sample_labels : Vec<SampleLabel>,
+    /// Number of core and border samples in each cluster.
+    pub cluster_sizes : Vec<usize>,
+}
+
+impl DbscanResult {
+    pub fn cluster_count(&self) -> usize {
+        self.cluster_sizes.len()
+    }
 }
 
 /// DBSCAN algorithm.
 ///
-/// Return (number of clusters, sample labels).
-/// Samples that are classified as noise have label == samples.len().
-pub fn dbscan<const N: usize>(samples : &[Vector<N>], epsilon : f64, min_pts : usize) -> (usize, Vec<usize>) {
-    let mut labels = vec![samples.len(); samples.len()];
-    let mut label_next = 0;
-    for index in 0..samples.len() {
-        if labels[index] == samples.len() {
-            let neighbour_indices = neighbours(samples, epsilon, index);
+/// Neighbourhood queries are answered by a [KdTree] built on samples. See [dbscan_with_index] to
+/// use a different spatial index.
+pub fn dbscan<P: Point>(samples : &[P], epsilon : f64, min_pts : usize) -> DbscanResult {
+    dbscan_with_index(samples, &KdTree::new(samples), epsilon, min_pts)
+}
+
+/// DBSCAN algorithm with neighbourhood queries answered by a spatial index built on samples.
+pub fn dbscan_with_index<P: Point, I: SpatialIndex<P>>(samples : &[P], index : &I, epsilon : f64, min_pts : usize) -> DbscanResult {
+    dbscan_with_neighbours(samples.len(), |sample_index| neighbours(samples, index, epsilon, sample_index), min_pts)
+}
+
+/// DBSCAN algorithm with distances given by metric.
+///
+/// Neighbourhood queries compare against every sample, so that this takes O(n^2) regardless of
+/// epsilon, but unlike [dbscan_precomputed] only O(n) memory.
+pub fn dbscan_with_metric<P, M: Metric<P>>(samples : &[P], metric : &M, epsilon : f64, min_pts : usize) -> DbscanResult {
+    let neighbours = |sample_index| (0..samples.len())
+        .filter(|&other| other != sample_index && metric.distance(&samples[sample_index], &samples[other]) < epsilon)
+        .collect();
+    dbscan_with_neighbours(samples.len(), neighbours, min_pts)
+}
+
+/// DBSCAN algorithm on precomputed distances.
+///
+/// Neighbourhood queries scan a row of distance matrix, so that this takes O(n^2) regardless of
+/// epsilon.
+pub fn dbscan_precomputed(distance_matrix : &DistanceMatrix, epsilon : f64, min_pts : usize) -> DbscanResult {
+    let sample_count = distance_matrix.sample_count();
+    let neighbours = |sample_index| (0..sample_count)
+        .filter(|&other| other != sample_index && distance_matrix.get(sample_index, other) < epsilon)
+        .collect();
+    dbscan_with_neighbours(sample_count, neighbours, min_pts)
+}
+
+/// DBSCAN algorithm given neighbours of each sample, excluding the sample itself.
+fn dbscan_with_neighbours<N>(sample_count : usize, mut neighbours : N, min_pts : usize) -> DbscanResult
+where
+    N: FnMut(usize) -> Vec<usize>
+{
+    let mut sample_labels = vec![SampleLabel::Noise; sample_count];
+    let mut cluster_sizes = Vec::new();
+    for sample_index in 0..sample_count {
+        if sample_labels[sample_index] == SampleLabel::Noise {
+            let neighbour_indices = neighbours(sample_index);
             if neighbour_indices.len() + 1 >= min_pts {
                 // We are a core point
-                labels[index] = label_next;
+                let cluster_index = cluster_sizes.len();
+                sample_labels[sample_index] = SampleLabel::Core(cluster_index);
+                let mut cluster_size = 1;
 
                 // Indices that may correspond to a core point.
                 let mut pending_indices = Vec::new();
 
                 // Initial set of pending indices.
                 for &neighbour_index in &neighbour_indices {
-                    if labels[neighbour_index] == samples.len() {
-                        labels[neighbour_index] = label_next;
+                    if sample_labels[neighbour_index] == SampleLabel::Noise {
+                        sample_labels[neighbour_index] = SampleLabel::Border(cluster_index);
+                        cluster_size += 1;
                         pending_indices.push(neighbour_index);
                     }
                 }
 
                 // Recursive expansion
                 while let Some(pending_index) = pending_indices.pop() {
-                    let neighbour_indices = neighbours(samples, epsilon, pending_index);
+                    let neighbour_indices = neighbours(pending_index);
                     if neighbour_indices.len() + 1 >= min_pts {
+                        sample_labels[pending_index] = SampleLabel::Core(cluster_index);
                         for &neighbour_index in &neighbour_indices {
-                            if labels[neighbour_index] == samples.len() {
-                                labels[neighbour_index] = label_next;
+                            if sample_labels[neighbour_index] == SampleLabel::Noise {
+                                sample_labels[neighbour_index] = SampleLabel::Border(cluster_index);
+                                cluster_size += 1;
                                 pending_indices.push(neighbour_index);
                             }
                         }
                     }
                 }
+
+                cluster_sizes.push(cluster_size);
             }
-            label_next += 1;
         }
     }
-    (label_next, labels)
+    DbscanResult { sample_labels, cluster_sizes }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::ball_tree::BallTree;
+    use crate::spatial::brute_force::BruteForce;
+    use crate::metric::SquaredEuclidean;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn test_spatial_index() {
+        let mut rng = thread_rng();
+        let samples = (0..1000).map(|_| Vector::from_array([rng.gen_range(0..40) as f64, rng.gen_range(0..40) as f64])).collect::<Vec<_>>();
+        for (epsilon, min_pts) in [(1.0, 1), (1.5, 3), (2.0, 5), (3.0, 10)] {
+            let expected = dbscan_with_index(&samples, &BruteForce(&samples), epsilon, min_pts);
+            assert_eq!(dbscan(&samples, epsilon, min_pts), expected);
+            assert_eq!(dbscan_with_index(&samples, &BallTree::new(&samples), epsilon, min_pts), expected);
+
+            // Squared distances give exactly the same neighbourhoods as the spatial indices.
+            let distance_matrix = DistanceMatrix::from_samples(&samples, |a, b| a.squared_distance(b));
+            assert_eq!(dbscan_precomputed(&distance_matrix, epsilon * epsilon, min_pts), expected);
+            assert_eq!(dbscan_with_metric(&samples, &SquaredEuclidean, epsilon * epsilon, min_pts), expected);
+        }
+    }
+
+    #[test]
+    fn test_result() {
+        // Two dense groups, a border sample attached to the second group and an isolated sample.
+        let samples = [
+            Vector::from_array([0.0, 0.0]),
+            Vector::from_array([10.0, 0.0]),
+            Vector::from_array([0.0, 0.5]),
+            Vector::from_array([5.0, 5.0]),
+            Vector::from_array([0.5, 0.0]),
+            Vector::from_array([10.0, 0.5]),
+            Vector::from_array([10.5, 0.0]),
+            Vector::from_array([11.4, 0.0]),
+        ];
+        let result = dbscan(&samples, 1.0, 3);
+        assert_eq!(result.sample_labels, vec![
+            SampleLabel::Core(0),
+            SampleLabel::Core(1),
+            SampleLabel::Core(0),
+            SampleLabel::Noise,
+            SampleLabel::Core(0),
+            SampleLabel::Core(1),
+            SampleLabel::Core(1),
+            SampleLabel::Border(1),
+        ]);
+        assert_eq!(result.cluster_count(), 2);
+        assert_eq!(result.cluster_sizes, vec![3, 4]);
+
+        // Noise discovered before any cluster does not consume a cluster index.
+        let result = dbscan(&samples[3..], 1.0, 2);
+        assert_eq!(result.sample_labels[0], SampleLabel::Noise);
+        assert_eq!(result.sample_labels[2].cluster(), Some(0));
+        assert_eq!(result.cluster_sizes, vec![3]);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::hierarchical::agglomerative::dendrogram::Dendrogram;
+use crate::hierarchical::agglomerative::disjoint_set::DisjointSet;
+use crate::spatial::SpatialIndex;
+use crate::spatial::kd_tree::KdTree;
+
+use math::prelude::*;
+
+/// Implementation of HDBSCAN (Hierarchical DBSCAN).
+///
+/// See Campello, Moulavi and Sander, "Density-Based Clustering Based on Hierarchical Density
+/// Estimates" (2013). Distances are replaced by mutual reachability distances
+/// max(core(a), core(b), distance(a, b)), which push samples in sparse regions apart. The
+/// single-linkage hierarchy of mutual reachability distances is then condensed by treating
+/// clusters of less than [Self::min_cluster_size] samples splitting off as samples leaving the
+/// parent cluster, and the most stable clusters in the condensed hierarchy are selected. Unlike
+/// [super::dbscan::dbscan], there is no global epsilon, so clusters of varying density can be
+/// found.
+#[derive(Debug, Copy, Clone)]
+pub struct Hdbscan {
+    /// Minimum number of samples in a cluster. Must be at least 2.
+    pub min_cluster_size : usize,
+    /// Number of samples (including itself) in the neighbourhood of a sample used to compute its
+    /// core distance. Must be at least 1.
+    ///
+    /// Larger values make clustering more conservative, with more samples declared as noise.
+    pub min_samples : usize,
+    /// Allow the root cluster containing every sample to be selected.
+    pub allow_single_cluster : bool,
+}
+
+/// Result of HDBSCAN.
+///
+/// Clusters are numbered contiguously from 0.
+#[derive(Debug, Clone)]
+pub struct HdbscanResult {
+    /// Cluster of each sample, or None if it is noise.
+    pub sample_labels : Vec<Option<usize>>,
+    /// Strength of membership of each sample in its cluster, from 0 to 1. Noise has probability 0.
+    pub sample_probabilities : Vec<f64>,
+    /// GLOSH outlier score of each sample, from 0 to 1, where larger values are more outlying.
+    pub sample_outlier_scores : Vec<f64>,
+    /// Number of samples in each cluster.
+    pub cluster_sizes : Vec<usize>,
+    /// Single-linkage dendrogram of mutual reachability distances.
+    pub dendrogram : Dendrogram,
+}
+
+impl HdbscanResult {
+    pub fn cluster_count(&self) -> usize {
+        self.cluster_sizes.len()
+    }
+}
+
+/// Return how much lambda is past birth, where both may be infinite.
+fn excess(lambda : f64, birth : f64) -> f64 {
+    if lambda == birth { 0.0 } else { lambda - birth }
+}
+
+impl Hdbscan {
+    /// Constructor.
+    ///
+    /// By default, min_samples is equal to min_cluster_size and the root cluster cannot be
+    /// selected.
+    pub fn new(min_cluster_size : usize) -> Self {
+        Self {
+            min_cluster_size,
+            min_samples : min_cluster_size,
+            allow_single_cluster : false,
+        }
+    }
+
+    /// Compute core distance of each sample, which is the distance to its min_samples-th nearest
+    /// sample counting itself.
+    ///
+    /// **Inputs**:  (samples) <br/>
+    /// **Outputs**: (core_distances)
+    pub fn core_distances<P: Point>(self, samples : &[P]) -> Vec<f64> {
+        assert_ne!(self.min_samples, 0);
+
+        let index = KdTree::new(samples);
+        samples
+            .iter()
+            .map(|sample| index.k_nearest(sample, self.min_samples).last().map_or(0.0, |&(_, distance)| distance))
+            .collect()
+    }
+
+    /// Compute minimum spanning tree of mutual reachability distances by Prim's algorithm in O(n^2)
+    /// time and O(n) memory.
+    ///
+    /// **Inputs**:  (samples, core_distances) <br/>
+    /// **Outputs**: (edges)
+    ///
+    /// Each edge is (index1, index2, mutual_reachability_distance), sorted by distance.
+    pub fn minimum_spanning_tree<P: Point>(self, samples : &[P], core_distances : &[f64]) -> Vec<(usize, usize, f64)> {
+        let sample_count = samples.len();
+        assert_eq!(core_distances.len(), sample_count);
+
+        let mut edges = Vec::with_capacity(sample_count.saturating_sub(1));
+        let mut in_tree = vec![false; sample_count];
+        let mut nearest = vec![(f64::INFINITY, 0); sample_count];
+        let mut current = 0;
+        for _ in 1..sample_count {
+            in_tree[current] = true;
+            for other in 0..sample_count {
+                if !in_tree[other] {
+                    let distance = samples[current].distance(&samples[other]).max(core_distances[current]).max(core_distances[other]);
+                    if nearest[other].0 > distance {
+                        nearest[other] = (distance, current);
+                    }
+                }
+            }
+
+            let next = (0..sample_count)
+                .filter(|&other| !in_tree[other])
+                .min_by(|&a, &b| f64::total_cmp(&nearest[a].0, &nearest[b].0))
+                .unwrap();
+            edges.push((nearest[next].1, next, nearest[next].0));
+            current = next;
+        }
+        edges.sort_by(|(_, _, a), (_, _, b)| f64::total_cmp(a, b));
+        edges
+    }
+
+    /// HDBSCAN algorithm.
+    pub fn run<P: Point>(self, samples : &[P]) -> HdbscanResult {
+        assert!(self.min_cluster_size >= 2);
+
+        let sample_count = samples.len();
+        let core_distances = self.core_distances(samples);
+        let edges = self.minimum_spanning_tree(samples, &core_distances);
+        let dendrogram = Dendrogram::from_merges(sample_count, edges.iter().copied());
+        if sample_count < self.min_cluster_size {
+            return HdbscanResult {
+                sample_labels : vec![None; sample_count],
+                sample_probabilities : vec![0.0; sample_count],
+                sample_outlier_scores : vec![0.0; sample_count],
+                cluster_sizes : Vec::new(),
+                dendrogram,
+            };
+        }
+
+        // 1: Binary merge tree, where nodes below sample_count are samples and node
+        //    sample_count + i is the cluster formed by the i-th merge.
+        let mut node_children = Vec::with_capacity(edges.len());
+        let mut node_heights = Vec::with_capacity(edges.len());
+        let mut node_sizes = vec![1; sample_count];
+        {
+            let mut disjoint_set = DisjointSet::new(sample_count);
+            let mut root_nodes = (0..sample_count).collect::<Vec<_>>();
+            for &(index1, index2, height) in &edges {
+                let root1 = disjoint_set.find(index1);
+                let root2 = disjoint_set.find(index2);
+                let (node1, node2) = (root_nodes[root1], root_nodes[root2]);
+                node_children.push((node1, node2));
+                node_heights.push(height);
+                node_sizes.push(node_sizes[node1] + node_sizes[node2]);
+
+                disjoint_set.merge(root1, root2);
+                root_nodes[disjoint_set.find(root1)] = node_sizes.len() - 1;
+            }
+        }
+        let node_count = node_sizes.len();
+
+        // 2: Condensed tree, traversed top-down in decreasing node order. Lambda is the reciprocal
+        //    of height, and every sample eventually leaves the deepest condensed cluster it is in
+        //    at some lambda.
+        let mut node_clusters = vec![None; node_count];
+        let mut cluster_parents = vec![None];
+        let mut cluster_births = vec![0.0];
+        let mut cluster_deaths = vec![0.0];
+        let mut cluster_stabilities = vec![0.0];
+        let mut sample_lambdas = vec![0.0; sample_count];
+        let mut sample_clusters = vec![0; sample_count];
+        node_clusters[node_count - 1] = Some(0);
+        for node in (sample_count..node_count).rev() {
+            let Some(cluster) = node_clusters[node] else { continue };
+            let (node1, node2) = node_children[node - sample_count];
+            let lambda = 1.0 / node_heights[node - sample_count];
+            cluster_deaths[cluster] = f64::max(cluster_deaths[cluster], lambda);
+
+            let large1 = node_sizes[node1] >= self.min_cluster_size;
+            let large2 = node_sizes[node2] >= self.min_cluster_size;
+            for (child, large) in [(node1, large1), (node2, large2)] {
+                if large && large1 && large2 {
+                    // True split into two new clusters.
+                    cluster_stabilities[cluster] += node_sizes[child] as f64 * excess(lambda, cluster_births[cluster]);
+                    node_clusters[child] = Some(cluster_parents.len());
+                    cluster_parents.push(Some(cluster));
+                    cluster_births.push(lambda);
+                    cluster_deaths.push(lambda);
+                    cluster_stabilities.push(0.0);
+                } else if large {
+                    // The cluster merely shrinks, and samples remaining contribute to stability
+                    // when they eventually leave.
+                    node_clusters[child] = Some(cluster);
+                } else {
+                    // Samples leave the cluster.
+                    cluster_stabilities[cluster] += node_sizes[child] as f64 * excess(lambda, cluster_births[cluster]);
+                    let mut stack = vec![child];
+                    while let Some(node) = stack.pop() {
+                        if node < sample_count {
+                            sample_lambdas[node] = lambda;
+                            sample_clusters[node] = cluster;
+                        } else {
+                            let (node1, node2) = node_children[node - sample_count];
+                            stack.push(node1);
+                            stack.push(node2);
+                        }
+                    }
+                }
+            }
+        }
+        let cluster_count = cluster_parents.len();
+
+        // 3: Select clusters bottom-up in decreasing cluster order, where a cluster is selected
+        //    if it is at least as stable as its selected descendants combined.
+        let mut cluster_selected = vec![false; cluster_count];
+        let mut cluster_has_children = vec![false; cluster_count];
+        let mut descendant_stabilities = vec![0.0; cluster_count];
+        for cluster in (0..cluster_count).rev() {
+            let stability = if !cluster_has_children[cluster] || cluster_stabilities[cluster] >= descendant_stabilities[cluster] {
+                cluster_selected[cluster] = true;
+                cluster_stabilities[cluster]
+            } else {
+                descendant_stabilities[cluster]
+            };
+            if let Some(parent) = cluster_parents[cluster] {
+                cluster_has_children[parent] = true;
+                descendant_stabilities[parent] += stability;
+            }
+        }
+        if !self.allow_single_cluster {
+            cluster_selected[0] = false;
+        }
+
+        // 4: Label clusters top-down, where descendants of a selected cluster share its label.
+        let mut cluster_labels = vec![None; cluster_count];
+        let mut selected_clusters = Vec::new();
+        for cluster in 0..cluster_count {
+            cluster_labels[cluster] = match cluster_parents[cluster].and_then(|parent| cluster_labels[parent]) {
+                Some(label) => Some(label),
+                None if cluster_selected[cluster] => {
+                    selected_clusters.push(cluster);
+                    Some(selected_clusters.len() - 1)
+                }
+                None => None,
+            };
+        }
+
+        // 5: Largest lambda at which any sample leaves each cluster or any of its descendants.
+        let mut subtree_deaths = cluster_deaths.clone();
+        for cluster in (1..cluster_count).rev() {
+            let parent = cluster_parents[cluster].unwrap();
+            subtree_deaths[parent] = f64::max(subtree_deaths[parent], subtree_deaths[cluster]);
+        }
+
+        let mut sample_labels = vec![None; sample_count];
+        let mut sample_probabilities = vec![0.0; sample_count];
+        let mut sample_outlier_scores = vec![0.0; sample_count];
+        let mut cluster_sizes = vec![0; selected_clusters.len()];
+        for sample_index in 0..sample_count {
+            let lambda = sample_lambdas[sample_index];
+            if let Some(label) = cluster_labels[sample_clusters[sample_index]] {
+                let death = cluster_deaths[selected_clusters[label]];
+                sample_labels[sample_index] = Some(label);
+                sample_probabilities[sample_index] = if death == 0.0 || !lambda.is_finite() { 1.0 } else { lambda.min(death) / death };
+                cluster_sizes[label] += 1;
+            }
+
+            let death = subtree_deaths[sample_clusters[sample_index]];
+            sample_outlier_scores[sample_index] = if death == 0.0 || !lambda.is_finite() {
+                0.0
+            } else if !death.is_finite() {
+                1.0
+            } else {
+                (death - lambda) / death
+            };
+        }
+
+        HdbscanResult { sample_labels, sample_probabilities, sample_outlier_scores, cluster_sizes, dendrogram }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+
+    fn disk(rng : &mut StdRng, center : [f64; 2], radius : f64, count : usize) -> Vec<Vector<2>> {
+        (0..count)
+            .map(|_| {
+                let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+                let distance = radius * rng.gen_range(0.0f64..1.0).sqrt();
+                Vector::from_array([center[0] + distance * angle.cos(), center[1] + distance * angle.sin()])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_varying_density() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut samples = Vec::new();
+        samples.extend(disk(&mut rng, [0.0, 0.0], 0.5, 60));
+        samples.extend(disk(&mut rng, [20.0, 0.0], 5.0, 60));
+        samples.extend(disk(&mut rng, [0.0, 20.0], 2.0, 60));
+        samples.extend([Vector::from_array([-30.0, -30.0]), Vector::from_array([40.0, 40.0]), Vector::from_array([-30.0, 40.0])]);
+
+        let result = Hdbscan::new(10).run(&samples);
+        assert_eq!(result.cluster_count(), 3);
+        assert_eq!(result.cluster_sizes.iter().sum::<usize>() + 3, samples.len(), "{:?}", result.sample_labels);
+        for group in 0..3 {
+            let labels = &result.sample_labels[group * 60..(group + 1) * 60];
+            assert!(labels[0].is_some());
+            assert!(labels.iter().all(|&label| label == labels[0]));
+            assert!((0..group).all(|other| result.sample_labels[other * 60] != labels[0]));
+        }
+        for sample_index in 180..183 {
+            assert_eq!(result.sample_labels[sample_index], None);
+            assert_eq!(result.sample_probabilities[sample_index], 0.0);
+            assert!(result.sample_outlier_scores[sample_index] > 0.5);
+        }
+        assert!(result.sample_probabilities.iter().all(|&probability| (0.0..=1.0).contains(&probability)));
+        assert!(result.sample_outlier_scores.iter().all(|&score| (0.0..=1.0).contains(&score)));
+        assert_eq!(result.dendrogram.len(), samples.len());
+    }
+
+    #[test]
+    fn test_small() {
+        assert!(Hdbscan::new(5).run::<Vector<2>>(&[]).sample_labels.is_empty());
+
+        let samples = [Vector::from_array([0.0, 0.0]), Vector::from_array([1.0, 0.0])];
+        assert_eq!(Hdbscan::new(5).run(&samples).sample_labels, vec![None, None]);
+
+        let samples = [Vector::from_array([0.0, 0.0]); 6];
+        let result = Hdbscan { allow_single_cluster : true, ..Hdbscan::new(2) }.run(&samples);
+        assert_eq!(result.sample_labels, vec![Some(0); 6]);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod affinity_propagation;
 pub mod dbscan;
+pub mod hdbscan;
+pub mod optics;
+pub mod sparse_affinity_propagation;
@@ -0,0 +1,404 @@
+use super::dbscan::DbscanResult;
+use super::dbscan::SampleLabel;
+
+use crate::spatial::SpatialIndex;
+use crate::spatial::kd_tree::KdTree;
+
+use math::prelude::*;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Implementation of OPTICS (Ordering Points To Identify the Clustering Structure).
+///
+/// See Ankerst, Breunig, Kriegel and Sander, "OPTICS: Ordering Points To Identify the Clustering
+/// Structure" (1999). Instead of a single clustering, samples are ordered such that samples in
+/// dense regions are adjacent, together with a reachability distance for each sample. Plotting
+/// reachability distances in that order gives valleys for clusters, from which clusterings can be
+/// extracted afterwards without rerunning the algorithm. See [OpticsResult::extract_dbscan] and
+/// [OpticsResult::extract_xi].
+#[derive(Debug, Copy, Clone)]
+pub struct Optics {
+    /// Number of samples (including itself) within epsilon of a sample for it to be a core sample.
+    pub min_pts : usize,
+    /// Largest epsilon considered.
+    ///
+    /// Extraction is only meaningful for epsilon no greater than this. The default of infinity
+    /// makes the algorithm O(n^2), and a smaller value speeds it up by limiting neighbourhood
+    /// queries.
+    pub max_epsilon : f64,
+}
+
+/// Result of OPTICS.
+#[derive(Debug, Clone)]
+pub struct OpticsResult {
+    /// Value of [Optics::min_pts] the result was computed with.
+    pub min_pts : usize,
+    /// Sample indices in the order they are processed.
+    pub ordering : Vec<usize>,
+    /// Reachability distance of each sample, or infinity if undefined.
+    pub reachability_distances : Vec<f64>,
+    /// Core distance of each sample, or infinity if it is not a core sample for max_epsilon.
+    pub core_distances : Vec<f64>,
+    /// Sample from which each sample is reached, or None if reachability distance is undefined.
+    pub predecessors : Vec<Option<usize>>,
+}
+
+impl Optics {
+    /// Constructor.
+    pub fn new(min_pts : usize) -> Self {
+        Self {
+            min_pts,
+            max_epsilon : f64::INFINITY,
+        }
+    }
+
+    /// OPTICS algorithm.
+    ///
+    /// Among samples not yet processed, the one with the smallest reachability distance is
+    /// processed next, with ties broken by the smallest index.
+    pub fn run<P: Point>(self, samples : &[P]) -> OpticsResult {
+        assert_ne!(self.min_pts, 0);
+
+        let sample_count = samples.len();
+        let index = KdTree::new(samples);
+
+        let mut ordering = Vec::with_capacity(sample_count);
+        let mut reachability_distances = vec![f64::INFINITY; sample_count];
+        let mut core_distances = vec![f64::INFINITY; sample_count];
+        let mut predecessors = vec![None; sample_count];
+
+        let mut processed = vec![false; sample_count];
+        let mut seeds = BinaryHeap::new();
+        let mut next_unprocessed = 0;
+        loop {
+            // Pop stale entries lazily instead of decreasing keys.
+            let sample_index = match seeds.pop() {
+                Some(Reverse((_, sample_index))) if processed[sample_index] => continue,
+                Some(Reverse((_, sample_index))) => sample_index,
+                None => {
+                    while next_unprocessed < sample_count && processed[next_unprocessed] {
+                        next_unprocessed += 1;
+                    }
+                    if next_unprocessed == sample_count {
+                        break;
+                    }
+                    next_unprocessed
+                }
+            };
+            processed[sample_index] = true;
+            ordering.push(sample_index);
+
+            let neighbour_indices = index.within_radius(&samples[sample_index], self.max_epsilon);
+            if neighbour_indices.len() >= self.min_pts {
+                let core_distance = index.k_nearest(&samples[sample_index], self.min_pts).last().unwrap().1;
+                core_distances[sample_index] = core_distance;
+                for neighbour_index in neighbour_indices {
+                    if !processed[neighbour_index] {
+                        let reachability_distance = f64::max(core_distance, samples[sample_index].distance(&samples[neighbour_index]));
+                        if reachability_distances[neighbour_index] > reachability_distance {
+                            reachability_distances[neighbour_index] = reachability_distance;
+                            predecessors[neighbour_index] = Some(sample_index);
+                            seeds.push(Reverse((TotalF64(reachability_distance), neighbour_index)));
+                        }
+                    }
+                }
+            }
+        }
+
+        OpticsResult { min_pts : self.min_pts, ordering, reachability_distances, core_distances, predecessors }
+    }
+}
+
+/// Wrapper ordering f64 by [f64::total_cmp].
+#[derive(Debug, Clone, Copy)]
+struct TotalF64(f64);
+
+impl PartialEq for TotalF64 {
+    fn eq(&self, other : &Self) -> bool {
+        self.0.total_cmp(&other.0).is_eq()
+    }
+}
+
+impl Eq for TotalF64 {}
+
+impl PartialOrd for TotalF64 {
+    fn partial_cmp(&self, other : &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalF64 {
+    fn cmp(&self, other : &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl OpticsResult {
+    /// Reachability distances in the order samples are processed, i.e. the reachability plot.
+    pub fn reachability_plot(&self) -> Vec<f64> {
+        self.ordering.iter().map(|&sample_index| self.reachability_distances[sample_index]).collect()
+    }
+
+    /// Extract clustering equivalent to [super::dbscan::dbscan] with the given epsilon, which
+    /// should be no greater than max_epsilon.
+    ///
+    /// Core samples, and hence clusters as sets of core samples, are identical to those of
+    /// [super::dbscan::dbscan]. As noted in the original paper, a border sample processed before
+    /// all of the core samples within epsilon of it is reported as noise, and a border sample
+    /// within epsilon of core samples from multiple clusters may be assigned to a different one.
+    /// Clusters are numbered in order of discovery along the ordering.
+    pub fn extract_dbscan(&self, epsilon : f64) -> DbscanResult {
+        let mut sample_labels = vec![SampleLabel::Noise; self.ordering.len()];
+        let mut cluster_sizes : Vec<usize> = Vec::new();
+        for &sample_index in &self.ordering {
+            let core = self.core_distances[sample_index] < epsilon;
+            if self.reachability_distances[sample_index] < epsilon {
+                // Reached from a core sample of the current cluster.
+                let cluster_index = cluster_sizes.len() - 1;
+                sample_labels[sample_index] = if core { SampleLabel::Core(cluster_index) } else { SampleLabel::Border(cluster_index) };
+                cluster_sizes[cluster_index] += 1;
+            } else if core {
+                sample_labels[sample_index] = SampleLabel::Core(cluster_sizes.len());
+                cluster_sizes.push(1);
+            }
+        }
+        DbscanResult { sample_labels, cluster_sizes }
+    }
+
+    /// Extract clustering by steep areas in the reachability plot.
+    ///
+    /// A cluster starts at a steep downward area, where reachability drops by a factor of at
+    /// least 1 - xi between consecutive samples, and ends at a matching steep upward area.
+    /// Clusters can be nested. This follows the formulation of Ankerst et al. with the corrections
+    /// of the implementation in scikit-learn, including predecessor correction. Steep areas may
+    /// contain up to min_pts consecutive samples that are not steep.
+    ///
+    /// **Inputs**:  (xi, min_cluster_size) <br/>
+    /// **Outputs**: (clusters, sample_labels)
+    ///
+    /// Each cluster is a range start..=end of positions in the ordering, and clusters are sorted
+    /// such that nested clusters come before the clusters containing them. Each sample is labelled
+    /// with the first cluster containing it that does not overlap an earlier labelled cluster, or
+    /// None if it is noise.
+    pub fn extract_xi(&self, xi : f64, min_cluster_size : usize) -> (Vec<(usize, usize)>, Vec<Option<usize>>) {
+        assert!(0.0 < xi && xi < 1.0);
+
+        let sample_count = self.ordering.len();
+        let xi_complement = 1.0 - xi;
+
+        let mut reachability_plot = self.reachability_plot();
+        reachability_plot.push(f64::INFINITY);
+        let predecessor_plot = self.ordering.iter().map(|&sample_index| self.predecessors[sample_index]).collect::<Vec<_>>();
+
+        // Comparisons involving NaN from infinity / infinity are false, as intended.
+        let ratios = (0..sample_count).map(|i| reachability_plot[i] / reachability_plot[i + 1]).collect::<Vec<_>>();
+        let steep_upward   = ratios.iter().map(|&ratio| ratio <= xi_complement).collect::<Vec<_>>();
+        let steep_downward = ratios.iter().map(|&ratio| ratio >= 1.0 / xi_complement).collect::<Vec<_>>();
+        let downward       = ratios.iter().map(|&ratio| ratio > 1.0).collect::<Vec<_>>();
+        let upward         = ratios.iter().map(|&ratio| ratio < 1.0).collect::<Vec<_>>();
+
+        // Steep downward areas as (start, end, maximum in between).
+        let mut steep_downward_areas : Vec<(usize, usize, f64)> = Vec::new();
+        let mut clusters = Vec::new();
+        let mut index = 0;
+        let mut maximum_in_between = 0.0;
+        for steep_index in (0..sample_count).filter(|&i| steep_upward[i] || steep_downward[i]) {
+            // Skip points that are part of an area already discovered.
+            if steep_index < index {
+                continue;
+            }
+
+            maximum_in_between = reachability_plot[index..=steep_index].iter().copied().fold(maximum_in_between, f64::max);
+
+            // Drop areas whose start is not sufficiently higher than the maximum in between.
+            if maximum_in_between.is_infinite() {
+                steep_downward_areas.clear();
+            } else {
+                steep_downward_areas.retain(|&(start, _, _)| maximum_in_between <= reachability_plot[start] * xi_complement);
+                for area in &mut steep_downward_areas {
+                    area.2 = f64::max(area.2, maximum_in_between);
+                }
+            }
+
+            if steep_downward[steep_index] {
+                let end = extend_region(&steep_downward, &upward, steep_index, self.min_pts);
+                steep_downward_areas.push((steep_index, end, 0.0));
+                index = end + 1;
+                maximum_in_between = reachability_plot[index];
+            } else {
+                let upward_start = steep_index;
+                let upward_end = extend_region(&steep_upward, &downward, steep_index, self.min_pts);
+                index = upward_end + 1;
+                maximum_in_between = reachability_plot[index];
+
+                let mut upward_clusters = Vec::new();
+                for &(downward_start, downward_end, downward_maximum_in_between) in &steep_downward_areas {
+                    let mut start = downward_start;
+                    let mut end = upward_end;
+
+                    if reachability_plot[end + 1] * xi_complement < downward_maximum_in_between {
+                        continue;
+                    }
+
+                    // Adjust start or end so that both sides are at about the same level.
+                    let downward_maximum = reachability_plot[downward_start];
+                    if downward_maximum * xi_complement >= reachability_plot[end + 1] {
+                        while reachability_plot[start + 1] > reachability_plot[end + 1] && start < downward_end {
+                            start += 1;
+                        }
+                    } else if reachability_plot[end + 1] * xi_complement >= downward_maximum {
+                        while reachability_plot[end - 1] > downward_maximum && end > upward_start {
+                            end -= 1;
+                        }
+                    }
+
+                    let Some((start, end)) = correct_predecessor(&reachability_plot, &predecessor_plot, &self.ordering, start, end) else { continue };
+                    if end - start + 1 < min_cluster_size || start > downward_end || end < upward_start {
+                        continue;
+                    }
+                    upward_clusters.push((start, end));
+                }
+
+                // Smaller clusters first.
+                upward_clusters.reverse();
+                clusters.extend(upward_clusters);
+            }
+        }
+
+        let mut position_labels = vec![None; sample_count];
+        let mut label_next = 0;
+        for &(start, end) in &clusters {
+            if position_labels[start..=end].iter().all(Option::is_none) {
+                position_labels[start..=end].fill(Some(label_next));
+                label_next += 1;
+            }
+        }
+
+        let mut sample_labels = vec![None; sample_count];
+        for (position, &sample_index) in self.ordering.iter().enumerate() {
+            sample_labels[sample_index] = position_labels[position];
+        }
+        (clusters, sample_labels)
+    }
+}
+
+/// Return the end of the maximal steep area starting at start, allowing up to min_pts consecutive
+/// points that are neither steep nor in the opposite direction.
+fn extend_region(steep : &[bool], opposite : &[bool], start : usize, min_pts : usize) -> usize {
+    let mut non_steep_count = 0;
+    let mut end = start;
+    for index in start..steep.len() {
+        if steep[index] {
+            non_steep_count = 0;
+            end = index;
+        } else if !opposite[index] {
+            non_steep_count += 1;
+            if non_steep_count > min_pts {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// Shrink cluster start..=end from the end until the sample at end is reached from a sample in the
+/// cluster, or its reachability is lower than that at start.
+fn correct_predecessor(reachability_plot : &[f64], predecessor_plot : &[Option<usize>], ordering : &[usize], start : usize, mut end : usize) -> Option<(usize, usize)> {
+    while start < end {
+        if reachability_plot[start] > reachability_plot[end] {
+            return Some((start, end));
+        }
+        if let Some(predecessor) = predecessor_plot[end] {
+            if ordering[start..end].contains(&predecessor) {
+                return Some((start, end));
+            }
+        }
+        end -= 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::dbscan::dbscan;
+
+    use rand::prelude::*;
+
+    #[test]
+    fn test_extract_dbscan() {
+        let mut rng = thread_rng();
+        let samples = (0..500).map(|_| Vector::from_array([rng.gen_range(0..30) as f64, rng.gen_range(0..30) as f64])).collect::<Vec<_>>();
+        let result = Optics::new(4).run(&samples);
+
+        let mut ordering = result.ordering.clone();
+        ordering.sort();
+        assert_eq!(ordering, (0..samples.len()).collect::<Vec<_>>());
+
+        for epsilon in [1.0, 1.5, 2.0, 3.0] {
+            let expected = dbscan(&samples, epsilon, 4);
+            let extracted = result.extract_dbscan(epsilon);
+            assert_eq!(extracted.cluster_count(), expected.cluster_count());
+
+            let mut cluster_mapping = vec![None; expected.cluster_count()];
+            for (&label, &expected_label) in std::iter::zip(&extracted.sample_labels, &expected.sample_labels) {
+                match (label, expected_label) {
+                    (SampleLabel::Core(cluster_index), SampleLabel::Core(expected_cluster_index)) => {
+                        assert_eq!(*cluster_mapping[expected_cluster_index].get_or_insert(cluster_index), cluster_index);
+                    }
+                    (SampleLabel::Border(_), SampleLabel::Border(_)) => {}
+                    (SampleLabel::Noise, SampleLabel::Border(_) | SampleLabel::Noise) => {}
+                    (label, expected_label) => panic!("{label:?} != {expected_label:?}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract_xi() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut samples = Vec::new();
+        for (center, radius) in [([0.0, 0.0], 0.5), ([20.0, 0.0], 3.0), ([0.0, 20.0], 1.0)] {
+            for _ in 0..50 {
+                samples.push(Vector::from_array([center[0] + rng.gen_range(-radius..radius), center[1] + rng.gen_range(-radius..radius)]));
+            }
+        }
+
+        let result = Optics::new(5).run(&samples);
+        let (clusters, sample_labels) = result.extract_xi(0.05, 5);
+
+        // Each group is exactly one of the clusters, possibly containing smaller clusters.
+        for group in 0..3 {
+            assert!(clusters.iter().any(|&(start, end)| {
+                let mut members = result.ordering[start..=end].to_vec();
+                members.sort();
+                members == (group * 50..(group + 1) * 50).collect::<Vec<_>>()
+            }));
+        }
+
+        // Labels never cross groups.
+        for group in 0..3 {
+            for other in 0..group {
+                for sample_index in group * 50..(group + 1) * 50 {
+                    for other_index in other * 50..(other + 1) * 50 {
+                        assert!(sample_labels[sample_index].is_none() || sample_labels[sample_index] != sample_labels[other_index]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_small() {
+        let result = Optics::new(3).run::<Vector<2>>(&[]);
+        assert!(result.ordering.is_empty());
+        assert!(result.extract_xi(0.05, 3).0.is_empty());
+
+        let result = Optics::new(3).run(&[Vector::from_array([0.0, 0.0])]);
+        assert_eq!(result.ordering, vec![0]);
+        assert_eq!(result.extract_dbscan(1.0).sample_labels, vec![SampleLabel::Noise]);
+    }
+}
@@ -0,0 +1,315 @@
+//! Affinity propagation on a sparse similarity graph.
+//!
+//! [super::affinity_propagation::AffinityPropagation] stores similarities, responsibilities and
+//! availabilities for every pair of samples, which takes O(n^2) memory. Here, only pairs connected
+//! by an edge in a [SimilarityGraph] are stored and updated, and missing edges are treated as
+//! having a similarity of negative infinity. Each iteration takes O(e) time and memory, where e is
+//! the number of edges.
+
+use super::affinity_propagation::median;
+use super::affinity_propagation::ConvergenceCriteria;
+use super::affinity_propagation::DidNotConverge;
+
+use crate::spatial::SpatialIndex;
+use crate::spatial::kd_tree::KdTree;
+
+use math::prelude::*;
+
+fn lerp(a : f64, low : f64, high : f64) -> f64 {
+    (1.0 - a) * low + a * high
+}
+
+/// Directed graph of similarities between samples, stored in compressed sparse row format.
+///
+/// Self edges are never stored, since self-similarities are given by the preference instead.
+#[derive(Debug, Clone)]
+pub struct SimilarityGraph {
+    sample_count : usize,
+    /// Edges from sample i are at row_offsets[i]..row_offsets[i+1], sorted by target.
+    row_offsets : Vec<usize>,
+    targets : Vec<usize>,
+    similarities : Vec<f64>,
+}
+
+impl SimilarityGraph {
+    /// Construct graph from edges (source, target, similarity).
+    ///
+    /// Self edges are ignored, and for duplicate edges only the first is kept.
+    pub fn from_edges<I>(sample_count : usize, edges : I) -> Self
+    where
+        I: IntoIterator<Item = (usize, usize, f64)>,
+    {
+        let mut edges = edges.into_iter().filter(|&(source, target, _)| source != target).collect::<Vec<_>>();
+        edges.sort_by_key(|&(source, target, _)| (source, target));
+        edges.dedup_by_key(|&mut (source, target, _)| (source, target));
+
+        let mut row_offsets = vec![0; sample_count + 1];
+        for &(source, target, _) in &edges {
+            assert!(source < sample_count && target < sample_count);
+            row_offsets[source + 1] += 1;
+        }
+        for i in 0..sample_count {
+            row_offsets[i + 1] += row_offsets[i];
+        }
+
+        let targets = edges.iter().map(|&(_, target, _)| target).collect();
+        let similarities = edges.iter().map(|&(_, _, similarity)| similarity).collect();
+        Self { sample_count, row_offsets, targets, similarities }
+    }
+
+    /// Construct graph connecting each sample with its k nearest other samples, in both
+    /// directions, with similarity being negative squared distance.
+    pub fn k_nearest<P: Point>(samples : &[P], k : usize) -> Self {
+        let index = KdTree::new(samples);
+        let mut edges = Vec::with_capacity(2 * k * samples.len());
+        for (i, sample) in samples.iter().enumerate() {
+            for (j, distance) in index.k_nearest(sample, k + 1) {
+                edges.push((i, j, -distance * distance));
+                edges.push((j, i, -distance * distance));
+            }
+        }
+        Self::from_edges(samples.len(), edges)
+    }
+
+    /// Construct graph connecting each pair of samples closer than radius, with similarity being
+    /// negative squared distance.
+    pub fn within_radius<P: Point>(samples : &[P], radius : f64) -> Self {
+        let index = KdTree::new(samples);
+        let mut edges = Vec::new();
+        for (i, sample) in samples.iter().enumerate() {
+            for j in index.within_radius(sample, radius) {
+                edges.push((i, j, -sample.squared_distance(&samples[j])));
+            }
+        }
+        Self::from_edges(samples.len(), edges)
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Median of similarities of all edges, or None if there are no edges.
+    ///
+    /// This is the usual choice of preference, which gives a moderate number of clusters.
+    pub fn median_similarity(&self) -> Option<f64> {
+        (!self.similarities.is_empty()).then(|| median(self.similarities.clone()))
+    }
+
+    /// Minimum of similarities of all edges, or None if there are no edges.
+    ///
+    /// This gives a small number of clusters.
+    pub fn minimum_similarity(&self) -> Option<f64> {
+        self.similarities.iter().copied().min_by(f64::total_cmp)
+    }
+
+    /// Iterate over (target, similarity) of edges from source.
+    pub fn edges(&self, source : usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.row_offsets[source]..self.row_offsets[source + 1];
+        std::iter::zip(&self.targets[range.clone()], &self.similarities[range]).map(|(&target, &similarity)| (target, similarity))
+    }
+}
+
+/// Affinity propagation on a sparse similarity graph.
+///
+/// The interface mirrors [super::affinity_propagation::AffinityPropagation].
+pub struct SparseAffinityPropagation {
+    sample_count : usize,
+
+    /// Same layout as [SimilarityGraph], with a self edge added to every row.
+    row_offsets : Vec<usize>,
+    targets : Vec<usize>,
+    /// Position of self edge of each sample.
+    diagonals : Vec<usize>,
+
+    similarities : Vec<f64>,
+    responsiblities : Vec<f64>,
+    availabilities : Vec<f64>,
+}
+
+impl SparseAffinityPropagation {
+    pub fn new(graph : &SimilarityGraph, preference : f64) -> Self {
+        let sample_count = graph.sample_count;
+        let edge_count = graph.edge_count() + sample_count;
+
+        let mut row_offsets = Vec::with_capacity(sample_count + 1);
+        let mut targets = Vec::with_capacity(edge_count);
+        let mut diagonals = Vec::with_capacity(sample_count);
+        let mut similarities = Vec::with_capacity(edge_count);
+        row_offsets.push(0);
+        for i in 0..sample_count {
+            let mut self_edge = Some((i, preference));
+            for (k, similarity) in graph.edges(i) {
+                if k > i {
+                    if let Some((i, preference)) = self_edge.take() {
+                        diagonals.push(targets.len());
+                        targets.push(i);
+                        similarities.push(preference);
+                    }
+                }
+                targets.push(k);
+                similarities.push(similarity);
+            }
+            if let Some((i, preference)) = self_edge.take() {
+                diagonals.push(targets.len());
+                targets.push(i);
+                similarities.push(preference);
+            }
+            row_offsets.push(targets.len());
+        }
+
+        let responsiblities = vec![0.0; edge_count];
+        let availabilities  = vec![0.0; edge_count];
+
+        Self { sample_count, row_offsets, targets, diagonals, similarities, responsiblities, availabilities }
+    }
+
+    pub fn update(&mut self, damping : f64) {
+        // Responsibilities update, where the max over k' != k is the largest value in the row
+        // unless k is where it is attained, in which case it is the second largest.
+        for i in 0..self.sample_count {
+            let range = self.row_offsets[i]..self.row_offsets[i + 1];
+
+            let mut first = (usize::MAX, f64::NEG_INFINITY);
+            let mut second = f64::NEG_INFINITY;
+            for position in range.clone() {
+                let value = self.availabilities[position] + self.similarities[position];
+                if value > first.1 {
+                    second = first.1;
+                    first = (position, value);
+                } else if value > second {
+                    second = value;
+                }
+            }
+
+            for position in range {
+                let max = if position == first.0 { second } else { first.1 };
+                let new_responsibility = self.similarities[position] - max;
+                self.responsiblities[position] = lerp(damping, new_responsibility, self.responsiblities[position]);
+            }
+        }
+
+        // Availabilities update, using sums of positive responsibilities in each column
+        // excluding the self edge.
+        let mut column_sums = vec![0.0; self.sample_count];
+        for i in 0..self.sample_count {
+            for position in self.row_offsets[i]..self.row_offsets[i + 1] {
+                if position != self.diagonals[i] {
+                    column_sums[self.targets[position]] += self.responsiblities[position].max(0.0);
+                }
+            }
+        }
+
+        for i in 0..self.sample_count {
+            for position in self.row_offsets[i]..self.row_offsets[i + 1] {
+                let k = self.targets[position];
+                let new_availability = if position == self.diagonals[i] {
+                    column_sums[k]
+                } else {
+                    (self.responsiblities[self.diagonals[k]] + column_sums[k] - self.responsiblities[position].max(0.0)).min(0.0)
+                };
+                self.availabilities[position] = lerp(damping, new_availability, self.availabilities[position]);
+            }
+        }
+    }
+
+    pub fn exemplers(&self) -> Vec<usize> {
+        (0..self.sample_count)
+            .filter(|&i| self.responsiblities[self.diagonals[i]] + self.availabilities[self.diagonals[i]] > 0.0)
+            .collect()
+    }
+
+    /// Return labels for each sample as indices into the exemplers array.
+    ///
+    /// A sample is labelled with the exempler it is connected to with the largest responsibility,
+    /// or None if it is not connected to any exempler.
+    pub fn labels(&self, exemplers : &[usize]) -> Vec<Option<usize>> {
+        (0..self.sample_count)
+            .map(|i| {
+                (self.row_offsets[i]..self.row_offsets[i + 1])
+                    .filter_map(|position| exemplers.binary_search(&self.targets[position]).ok().map(|label| (label, self.responsiblities[position])))
+                    .max_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+                    .map(|(label, _)| label)
+            })
+            .collect()
+    }
+}
+
+/// Implementation of affinity propagation clustering on a sparse similarity graph.
+///
+/// See [super::affinity_propagation::affinity_propagation]. See [SimilarityGraph::median_similarity]
+/// and [SimilarityGraph::minimum_similarity] for choosing preference.
+/// Return exemplers and labels, or [DidNotConverge] if criteria.max_iterations is reached first.
+pub fn sparse_affinity_propagation(graph : &SimilarityGraph, preference : f64, damping : f64, criteria : ConvergenceCriteria) -> Result<(Vec<usize>, Vec<Option<usize>>), DidNotConverge> {
+    let mut ap = SparseAffinityPropagation::new(graph, preference);
+    let (exemplers, _) = criteria.iterate(|| { ap.update(damping); ap.exemplers() })?;
+    let labels = ap.labels(&exemplers);
+    Ok((exemplers, labels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::affinity_propagation::affinity_propagation;
+
+    use rand::prelude::*;
+
+    fn samples() -> Vec<Vector<2>> {
+        let mut rng = StdRng::seed_from_u64(0);
+        [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]]
+            .into_iter()
+            .flat_map(|center| (0..20).map(|_| Vector::from_array([center[0] + rng.gen_range(-1.0..1.0), center[1] + rng.gen_range(-1.0..1.0)])).collect::<Vec<_>>())
+            .collect()
+    }
+
+    #[test]
+    fn test_complete_graph() {
+        let samples = samples();
+        let graph = SimilarityGraph::within_radius(&samples, f64::INFINITY);
+        assert_eq!(graph.edge_count(), samples.len() * (samples.len() - 1));
+
+        let preference = graph.median_similarity().unwrap();
+        let (exemplers, labels) = sparse_affinity_propagation(&graph, preference, 0.7, ConvergenceCriteria::default()).unwrap();
+        let (expected_exemplers, expected_labels) = affinity_propagation(&samples, |a, b| -a.squared_distance(b), preference, 0.7, ConvergenceCriteria::default()).unwrap();
+        assert_eq!(exemplers, expected_exemplers);
+        assert_eq!(labels, expected_labels.into_iter().map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_k_nearest() {
+        let samples = samples();
+        let graph = SimilarityGraph::k_nearest(&samples, 5);
+        assert!(graph.edge_count() <= 2 * 5 * samples.len());
+        for i in 0..samples.len() {
+            assert!(graph.edges(i).all(|(k, _)| k != i && graph.edges(k).any(|(j, _)| j == i)));
+        }
+
+        let mut ap = SparseAffinityPropagation::new(&graph, -4.0);
+        for _ in 0..200 {
+            ap.update(0.7);
+        }
+        let exemplers = ap.exemplers();
+        let labels = ap.labels(&exemplers);
+        assert!(exemplers.len() >= 3);
+        for group in 0..3 {
+            for i in group * 20..(group + 1) * 20 {
+                let label = labels[i].unwrap();
+                assert_eq!(exemplers[label] / 20, group);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_edges() {
+        let graph = SimilarityGraph::from_edges(3, [(2, 0, -1.0), (0, 0, 5.0), (0, 2, -2.0), (0, 1, -3.0), (0, 2, -4.0)]);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.edges(0).collect::<Vec<_>>(), vec![(1, -3.0), (2, -2.0)]);
+        assert_eq!(graph.edges(1).count(), 0);
+        assert_eq!(graph.edges(2).collect::<Vec<_>>(), vec![(0, -1.0)]);
+        assert_eq!(graph.median_similarity(), Some(-2.0));
+        assert_eq!(graph.minimum_similarity(), Some(-3.0));
+    }
+}
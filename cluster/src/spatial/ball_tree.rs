@@ -0,0 +1,120 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+struct Node<P: Point> {
+    start : usize,
+    end : usize,
+    center : P,
+    radius : f64,
+    /// (left, right) if the node is not a leaf.
+    children : Option<(usize, usize)>,
+}
+
+/// Ball tree.
+///
+/// Each node stores a ball, given by the mean of its points and the largest distance from the
+/// mean to any of its points. Points are split at the median along the dimension of largest
+/// spread, so that the tree is balanced with depth O(log n).
+#[derive(Debug, Clone)]
+pub struct BallTree<'a, P: Point> {
+    points : &'a [P],
+    indices : Vec<usize>,
+    nodes : Vec<Node<P>>,
+}
+
+impl<'a, P: Point> BallTree<'a, P> {
+    /// Build tree in O(n log n).
+    pub fn new(points : &'a [P]) -> Self {
+        let mut tree = Self { points, indices : (0..points.len()).collect(), nodes : Vec::new() };
+        if !points.is_empty() {
+            tree.build(0, points.len());
+        }
+        tree
+    }
+
+    fn build(&mut self, start : usize, end : usize) -> usize {
+        let dimension = self.points[0].dimension();
+
+        let mut center = P::zero(dimension);
+        for &index in &self.indices[start..end] {
+            center += self.points[index].clone();
+        }
+        center /= (end - start) as f64;
+
+        let radius = self.indices[start..end].iter().map(|&index| self.points[index].distance(&center)).fold(0.0, f64::max);
+
+        let node_index = self.nodes.len();
+        self.nodes.push(Node { start, end, center, radius, children : None });
+        if end - start > LEAF_SIZE {
+            let (_, middle) = partition(self.points, &mut self.indices[start..end]);
+            let left  = self.build(start, start + middle);
+            let right = self.build(start + middle, end);
+            self.nodes[node_index].children = Some((left, right));
+        }
+        node_index
+    }
+
+    /// Return (lower, scale) where lower is a lower bound on the distance from query to any point
+    /// in node.
+    fn lower_bound(&self, node_index : usize, query : &P) -> (f64, f64) {
+        let node = &self.nodes[node_index];
+        let distance = node.center.distance(query);
+        (distance - node.radius, distance + node.radius)
+    }
+}
+
+impl<'a, P: Point> SpatialIndex<P> for BallTree<'a, P> {
+    fn within_radius(&self, query : &P, radius : f64) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(node_index) = stack.pop() {
+            let (lower, scale) = self.lower_bound(node_index, query);
+            if prunable(lower, scale, radius) {
+                continue;
+            }
+
+            let node = &self.nodes[node_index];
+            match node.children {
+                Some((left, right)) => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+                None => result.extend(self.indices[node.start..node.end].iter().copied().filter(|&index| self.points[index].squared_distance(query) < radius * radius)),
+            }
+        }
+        result.sort_unstable();
+        result
+    }
+
+    fn k_nearest(&self, query : &P, k : usize) -> Vec<(usize, f64)> {
+        let mut result = KNearest::new(k);
+        let mut stack = if self.nodes.is_empty() || k == 0 { vec![] } else { vec![(0, self.lower_bound(0, query))] };
+        while let Some((node_index, (lower, scale))) = stack.pop() {
+            if prunable(lower, scale, result.distance()) {
+                continue;
+            }
+
+            let node = &self.nodes[node_index];
+            match node.children {
+                Some((left, right)) => {
+                    // Visit nearer child first by pushing it last.
+                    let left_bound  = self.lower_bound(left, query);
+                    let right_bound = self.lower_bound(right, query);
+                    if left_bound.0 <= right_bound.0 {
+                        stack.push((right, right_bound));
+                        stack.push((left, left_bound));
+                    } else {
+                        stack.push((left, left_bound));
+                        stack.push((right, right_bound));
+                    }
+                }
+                None => {
+                    for &index in &self.indices[node.start..node.end] {
+                        result.push(Candidate { squared_distance : self.points[index].squared_distance(query), index });
+                    }
+                }
+            }
+        }
+        result.into_sorted_vec()
+    }
+}
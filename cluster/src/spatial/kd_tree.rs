@@ -0,0 +1,102 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+struct Node {
+    start : usize,
+    end : usize,
+    /// (dimension, value, left, right) if the node is not a leaf.
+    split : Option<(usize, f64, usize, usize)>,
+}
+
+/// K-d tree.
+///
+/// Each internal node splits its points at the median along the dimension of largest spread, so
+/// that the tree is balanced with depth O(log n).
+#[derive(Debug, Clone)]
+pub struct KdTree<'a, P: Point> {
+    points : &'a [P],
+    indices : Vec<usize>,
+    nodes : Vec<Node>,
+}
+
+impl<'a, P: Point> KdTree<'a, P> {
+    /// Build tree in O(n log n).
+    pub fn new(points : &'a [P]) -> Self {
+        let mut tree = Self { points, indices : (0..points.len()).collect(), nodes : Vec::new() };
+        if !points.is_empty() {
+            tree.build(0, points.len());
+        }
+        tree
+    }
+
+    fn build(&mut self, start : usize, end : usize) -> usize {
+        let node_index = self.nodes.len();
+        self.nodes.push(Node { start, end, split : None });
+        if end - start > LEAF_SIZE {
+            let (dimension, middle) = partition(self.points, &mut self.indices[start..end]);
+            let value = self.points[self.indices[start + middle]][dimension];
+            let left  = self.build(start, start + middle);
+            let right = self.build(start + middle, end);
+            self.nodes[node_index].split = Some((dimension, value, left, right));
+        }
+        node_index
+    }
+}
+
+impl<'a, P: Point> SpatialIndex<P> for KdTree<'a, P> {
+    fn within_radius(&self, query : &P, radius : f64) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            match node.split {
+                Some((dimension, value, left, right)) => {
+                    // Points in left are no greater and points in right are no less than value
+                    // along dimension.
+                    let scale = query[dimension].abs() + value.abs();
+                    if !prunable(query[dimension] - value, scale, radius) {
+                        stack.push(left);
+                    }
+                    if !prunable(value - query[dimension], scale, radius) {
+                        stack.push(right);
+                    }
+                }
+                None => result.extend(self.indices[node.start..node.end].iter().copied().filter(|&index| self.points[index].squared_distance(query) < radius * radius)),
+            }
+        }
+        result.sort_unstable();
+        result
+    }
+
+    fn k_nearest(&self, query : &P, k : usize) -> Vec<(usize, f64)> {
+        let mut result = KNearest::new(k);
+        let mut stack = if self.nodes.is_empty() || k == 0 { vec![] } else { vec![(0, 0.0, 0.0)] };
+        while let Some((node_index, lower, scale)) = stack.pop() {
+            if prunable(lower, scale, result.distance()) {
+                continue;
+            }
+
+            let node = &self.nodes[node_index];
+            match node.split {
+                Some((dimension, value, left, right)) => {
+                    // Visit nearer child first by pushing it last.
+                    let difference = query[dimension] - value;
+                    let scale = query[dimension].abs() + value.abs();
+                    if difference <= 0.0 {
+                        stack.push((right, -difference, scale));
+                        stack.push((left, 0.0, 0.0));
+                    } else {
+                        stack.push((left, difference, scale));
+                        stack.push((right, 0.0, 0.0));
+                    }
+                }
+                None => {
+                    for &index in &self.indices[node.start..node.end] {
+                        result.push(Candidate { squared_distance : self.points[index].squared_distance(query), index });
+                    }
+                }
+            }
+        }
+        result.into_sorted_vec()
+    }
+}
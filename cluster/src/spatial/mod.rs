@@ -0,0 +1,205 @@
+//! Spatial indices for fast neighbourhood queries.
+//!
+//! Both indices borrow the points they are built on and answer queries with indices into that
+//! slice. A [kd_tree::KdTree] is usually the fastest in low dimensions, while a
+//! [ball_tree::BallTree] degrades more gracefully as the dimension grows.
+
+pub mod kd_tree;
+pub mod ball_tree;
+
+use math::prelude::*;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Maximum number of points in a leaf node.
+pub(crate) const LEAF_SIZE : usize = 16;
+
+/// Relative slack for pruning.
+///
+/// Lower bounds on distances to nodes are loosened by this relative margin, and a node is only
+/// pruned if it is farther than the query radius by more than this relative margin as well, so
+/// that rounding errors never exclude a point that a brute force search would include.
+pub(crate) const SLACK : f64 = 1e-12;
+
+/// Return true if a node whose points are at least lower away can be skipped in a search for
+/// points closer than radius.
+///
+/// Here, lower is the lower bound computed as difference of two possibly large quantities whose
+/// total magnitude is scale.
+pub(crate) fn prunable(lower : f64, scale : f64, radius : f64) -> bool {
+    lower - SLACK * scale > radius * (1.0 + SLACK)
+}
+
+/// Common interface of spatial indices.
+pub trait SpatialIndex<P: Point> {
+    /// Indices of all points whose distance to query is strictly less than radius, in ascending
+    /// order.
+    ///
+    /// This matches exactly a brute force search comparing `squared_distance` against
+    /// `radius * radius`.
+    fn within_radius(&self, query : &P, radius : f64) -> Vec<usize>;
+
+    /// Indices of and distances to the k nearest points to query, sorted by distance and then by
+    /// index.
+    ///
+    /// Fewer than k points are returned if there are fewer than k points in total.
+    fn k_nearest(&self, query : &P, k : usize) -> Vec<(usize, f64)>;
+}
+
+/// Reorder indices so that the median along the dimension of largest spread is at the middle.
+///
+/// Return (dimension, middle), where all points before middle are no greater and all points from
+/// middle onwards are no less than the point at middle along dimension.
+pub(crate) fn partition<P: Point>(points : &[P], indices : &mut [usize]) -> (usize, usize) {
+    let dimension_count = points[indices[0]].dimension();
+    let dimension = (0..dimension_count)
+        .map(|dimension| {
+            let (min, max) = indices.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &index| (min.min(points[index][dimension]), max.max(points[index][dimension])));
+            (dimension, max - min)
+        })
+        .max_by(|(_, a), (_, b)| f64::total_cmp(a, b))
+        .map_or(0, |(dimension, _)| dimension);
+
+    let middle = indices.len() / 2;
+    indices.select_nth_unstable_by(middle, |&a, &b| f64::total_cmp(&points[a][dimension], &points[b][dimension]));
+    (dimension, middle)
+}
+
+/// Candidate for k nearest neighbours ordered by squared distance and then by index.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Candidate {
+    pub(crate) squared_distance : f64,
+    pub(crate) index : usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other : &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other : &Self) -> Ordering {
+        f64::total_cmp(&self.squared_distance, &other.squared_distance).then(self.index.cmp(&other.index))
+    }
+}
+
+/// Bounded max-heap of the best k candidates seen so far.
+pub(crate) struct KNearest {
+    k : usize,
+    heap : BinaryHeap<Candidate>,
+}
+
+impl KNearest {
+    pub(crate) fn new(k : usize) -> Self {
+        Self { k, heap : BinaryHeap::with_capacity(k + 1) }
+    }
+
+    /// Current pruning distance, beyond which no point can enter the result.
+    pub(crate) fn distance(&self) -> f64 {
+        if self.heap.len() < self.k { f64::INFINITY } else { self.heap.peek().map_or(f64::INFINITY, |candidate| candidate.squared_distance.sqrt()) }
+    }
+
+    pub(crate) fn push(&mut self, candidate : Candidate) {
+        if self.k == 0 {
+            return;
+        }
+
+        if self.heap.len() < self.k {
+            self.heap.push(candidate);
+        } else if candidate < *self.heap.peek().unwrap() {
+            self.heap.pop();
+            self.heap.push(candidate);
+        }
+    }
+
+    pub(crate) fn into_sorted_vec(self) -> Vec<(usize, f64)> {
+        self.heap.into_sorted_vec().into_iter().map(|candidate| (candidate.index, candidate.squared_distance.sqrt())).collect()
+    }
+}
+
+/// Brute force neighbourhood queries, which spatial indices are tested against.
+#[cfg(test)]
+pub(crate) mod brute_force {
+    use super::*;
+
+    pub(crate) fn brute_force_within_radius<P: Point>(points : &[P], query : &P, radius : f64) -> Vec<usize> {
+        (0..points.len()).filter(|&index| points[index].squared_distance(query) < radius * radius).collect()
+    }
+
+    pub(crate) fn brute_force_k_nearest<P: Point>(points : &[P], query : &P, k : usize) -> Vec<(usize, f64)> {
+        let mut candidates = (0..points.len()).map(|index| Candidate { squared_distance : points[index].squared_distance(query), index }).collect::<Vec<_>>();
+        candidates.sort();
+        candidates.into_iter().take(k).map(|candidate| (candidate.index, candidate.squared_distance.sqrt())).collect()
+    }
+
+    /// [SpatialIndex] on top of brute force queries.
+    pub(crate) struct BruteForce<'a, P: Point>(pub(crate) &'a [P]);
+
+    impl<P: Point> SpatialIndex<P> for BruteForce<'_, P> {
+        fn within_radius(&self, query : &P, radius : f64) -> Vec<usize> {
+            brute_force_within_radius(self.0, query, radius)
+        }
+
+        fn k_nearest(&self, query : &P, k : usize) -> Vec<(usize, f64)> {
+            brute_force_k_nearest(self.0, query, k)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::brute_force::*;
+    use super::kd_tree::KdTree;
+    use super::ball_tree::BallTree;
+
+    use rand::prelude::*;
+
+    fn check<P: Point, I: SpatialIndex<P>>(points : &[P], index : &I, queries : &[P]) {
+        for query in queries {
+            for radius in [0.0, 0.5, 1.0, 2.0, 5.0] {
+                assert_eq!(index.within_radius(query, radius), brute_force_within_radius(points, query, radius));
+            }
+            for k in [0, 1, 5, 50, points.len() + 1] {
+                assert_eq!(index.k_nearest(query, k), brute_force_k_nearest(points, query, k));
+            }
+        }
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut rng = thread_rng();
+
+        // Integer coordinates give plenty of duplicates and points exactly on the radius.
+        let points = (0..500).map(|_| Vector::from_array([rng.gen_range(0..8) as f64, rng.gen_range(0..8) as f64])).collect::<Vec<_>>();
+        let queries = (0..20).map(|_| Vector::from_array([rng.gen_range(0..8) as f64, rng.gen_range(0..8) as f64])).collect::<Vec<_>>();
+        check(&points, &KdTree::new(&points), &queries);
+        check(&points, &BallTree::new(&points), &queries);
+
+        let points = (0..500).map(|_| DVector::from_fn(5, |_| rng.gen_range(-2.0..2.0))).collect::<Vec<_>>();
+        let queries = (0..20).map(|_| DVector::from_fn(5, |_| rng.gen_range(-2.0..2.0))).collect::<Vec<_>>();
+        check(&points, &KdTree::new(&points), &queries);
+        check(&points, &BallTree::new(&points), &queries);
+    }
+
+    #[test]
+    fn test_small() {
+        let points : [Vector<2>; 0] = [];
+        assert!(KdTree::new(&points).within_radius(&Vector::zero(), 1.0).is_empty());
+        assert!(BallTree::new(&points).k_nearest(&Vector::zero(), 3).is_empty());
+
+        let points = [Vector::from_array([1.0, 1.0])];
+        assert_eq!(KdTree::new(&points).k_nearest(&Vector::zero(), 3), vec![(0, 2.0f64.sqrt())]);
+        assert_eq!(BallTree::new(&points).within_radius(&Vector::zero(), 2.0), vec![0]);
+    }
+}
@@ -0,0 +1,17 @@
+//! Fixtures shared by tests across the crate.
+
+/// Levenshtein distance between strings, for tests on samples which are not points.
+pub(crate) fn levenshtein(a : &str, b : &str) -> f64 {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &y) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(x != y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()] as f64
+}
@@ -0,0 +1,228 @@
+use crate::lu::Lu;
+use crate::lu::SingularMatrixError;
+
+use std::ops::Index;
+use std::ops::IndexMut;
+
+use std::ops::Add;
+use std::ops::Sub;
+use std::ops::Mul;
+use std::ops::Div;
+
+use std::ops::AddAssign;
+use std::ops::SubAssign;
+use std::ops::MulAssign;
+use std::ops::DivAssign;
+
+/// Square matrix whose dimension is only known at runtime.
+///
+/// This is the heap-allocated counterpart of [crate::matrix::Matrix]. Values are stored in
+/// row-major order. Binary operations between matrices of different dimensions panic.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DMatrix {
+    dimension : usize,
+    values : Vec<f64>,
+}
+
+impl DMatrix {
+    pub fn from_fn<F>(dimension : usize, mut cb : F) -> Self
+    where
+        F: FnMut((usize, usize)) -> f64
+    {
+        let mut values = Vec::with_capacity(dimension * dimension);
+        for j in 0..dimension {
+            for i in 0..dimension {
+                values.push(cb((j, i)));
+            }
+        }
+        Self { dimension, values }
+    }
+
+    /// Construct from values in row-major order.
+    pub fn from_vec(dimension : usize, values : Vec<f64>) -> Self {
+        assert_eq!(dimension * dimension, values.len());
+        Self { dimension, values }
+    }
+
+    pub fn into_vec(self) -> Vec<f64> {
+        self.values
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [f64] {
+        &mut self.values
+    }
+
+    /// Number of rows (and columns) of the matrix.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+impl Index<(usize, usize)> for DMatrix {
+    type Output = f64;
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        assert!(index.0 < self.dimension && index.1 < self.dimension);
+        &self.values[index.0 * self.dimension + index.1]
+    }
+}
+
+impl IndexMut<(usize, usize)> for DMatrix {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+        assert!(index.0 < self.dimension && index.1 < self.dimension);
+        &mut self.values[index.0 * self.dimension + index.1]
+    }
+}
+
+impl Add<DMatrix> for DMatrix { type Output = Self; fn add(mut self, rhs: Self) -> Self::Output { self += rhs; self } }
+impl Sub<DMatrix> for DMatrix { type Output = Self; fn sub(mut self, rhs: Self) -> Self::Output { self -= rhs; self } }
+impl Mul<DMatrix> for DMatrix { type Output = Self; fn mul(mut self, rhs: Self) -> Self::Output { self *= rhs; self } }
+impl Div<DMatrix> for DMatrix { type Output = Self; fn div(mut self, rhs: Self) -> Self::Output { self /= rhs; self } }
+
+impl Add<f64> for DMatrix { type Output = Self; fn add(mut self, rhs: f64) -> Self::Output { self += rhs; self } }
+impl Sub<f64> for DMatrix { type Output = Self; fn sub(mut self, rhs: f64) -> Self::Output { self -= rhs; self } }
+impl Mul<f64> for DMatrix { type Output = Self; fn mul(mut self, rhs: f64) -> Self::Output { self *= rhs; self } }
+impl Div<f64> for DMatrix { type Output = Self; fn div(mut self, rhs: f64) -> Self::Output { self /= rhs; self } }
+
+impl AddAssign<DMatrix> for DMatrix { fn add_assign(&mut self, rhs: Self) { assert_eq!(self.dimension, rhs.dimension); self.values.iter_mut().zip(rhs.values).for_each(|(a, b)| { *a += b }) } }
+impl SubAssign<DMatrix> for DMatrix { fn sub_assign(&mut self, rhs: Self) { assert_eq!(self.dimension, rhs.dimension); self.values.iter_mut().zip(rhs.values).for_each(|(a, b)| { *a -= b }) } }
+impl MulAssign<DMatrix> for DMatrix { fn mul_assign(&mut self, rhs: Self) { assert_eq!(self.dimension, rhs.dimension); self.values.iter_mut().zip(rhs.values).for_each(|(a, b)| { *a *= b }) } }
+impl DivAssign<DMatrix> for DMatrix { fn div_assign(&mut self, rhs: Self) { assert_eq!(self.dimension, rhs.dimension); self.values.iter_mut().zip(rhs.values).for_each(|(a, b)| { *a /= b }) } }
+
+impl AddAssign<f64> for DMatrix { fn add_assign(&mut self, rhs: f64) { self.values.iter_mut().for_each(|a| { *a += rhs }) } }
+impl SubAssign<f64> for DMatrix { fn sub_assign(&mut self, rhs: f64) { self.values.iter_mut().for_each(|a| { *a -= rhs }) } }
+impl MulAssign<f64> for DMatrix { fn mul_assign(&mut self, rhs: f64) { self.values.iter_mut().for_each(|a| { *a *= rhs }) } }
+impl DivAssign<f64> for DMatrix { fn div_assign(&mut self, rhs: f64) { self.values.iter_mut().for_each(|a| { *a /= rhs }) } }
+
+impl DMatrix {
+    pub fn zero(dimension : usize) -> Self {
+        Self { dimension, values : vec![0.0; dimension * dimension] }
+    }
+
+    pub fn one(dimension : usize) -> Self {
+        let mut result = Self::zero(dimension);
+        for i in 0..dimension {
+            result[(i, i)] = 1.0;
+        }
+        result
+    }
+}
+
+impl DMatrix {
+    /// Inverse computed by LU decomposition.
+    ///
+    /// Panic if the matrix is singular. See [Self::try_inverse] for a fallible alternative.
+    pub fn inverse(&self) -> Self {
+        self.try_inverse().expect("matrix is singular")
+    }
+
+    /// Inverse computed by LU decomposition.
+    pub fn try_inverse(&self) -> Result<Self, SingularMatrixError> {
+        Ok(Lu::new(self)?.inverse())
+    }
+
+    /// Determinant computed by LU decomposition.
+    pub fn determinant(&self) -> f64 {
+        Lu::new(self).map_or(0.0, |lu| lu.determinant())
+    }
+}
+
+impl std::fmt::Display for DMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut prefix = "[";
+        for j in 0..self.dimension {
+            write!(f, "{prefix}")?;
+            write!(f, "[")?;
+
+            let mut delim = "";
+            for i in 0..self.dimension {
+                write!(f, "{delim}")?;
+                write!(f, "{value}", value = self[(j, i)])?;
+                delim = " ";
+            }
+
+            write!(f, "]")?;
+            prefix = "\n ";
+        }
+        writeln!(f, "]")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    use rand::prelude::*;
+
+    fn random_matrix(dimension : usize) -> DMatrix {
+        DMatrix::from_fn(dimension, |_| thread_rng().gen_range(-1.0..1.0))
+    }
+
+    fn matrix_inverse_impl(dimension : usize) {
+        let a = random_matrix(dimension);
+        let b = a.inverse();
+
+        let result = a.clone().inner_product(b.clone());
+        let expected = DMatrix::one(dimension);
+
+        eprintln!("=====================");
+        eprintln!("Matrix a:");
+        eprintln!("=====================");
+        eprintln!("{a}");
+
+        eprintln!("=====================");
+        eprintln!("Matrix b:");
+        eprintln!("=====================");
+        eprintln!("{b}");
+
+        eprintln!("=====================");
+        eprintln!("Matrix a * b");
+        eprintln!("=====================");
+        eprintln!("{result}");
+
+        for j in 0..dimension {
+            for i in 0..dimension {
+                assert!((result[(j, i)] - expected[(j, i)]).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test] fn matrix_inverse_1()  { matrix_inverse_impl(1); }
+    #[test] fn matrix_inverse_2()  { matrix_inverse_impl(2); }
+    #[test] fn matrix_inverse_5()  { matrix_inverse_impl(5); }
+    #[test] fn matrix_inverse_10() { matrix_inverse_impl(10); }
+
+    fn matrix_determinant_impl(dimension : usize) {
+        let a = random_matrix(dimension);
+        let b = a.inverse();
+
+        let det_a = a.determinant();
+        let det_b = b.determinant();
+
+        let result = det_a * det_b;
+        let expected = 1.0;
+
+        eprintln!("det(a)          = {det_a}");
+        eprintln!("det(b)          = {det_b}");
+        eprintln!("det(a) * det(b) = {result}");
+
+        assert!((result - expected).abs() < 1e-5)
+    }
+
+    #[test] fn matrix_determinant_1()  { matrix_determinant_impl(1); }
+    #[test] fn matrix_determinant_2()  { matrix_determinant_impl(2); }
+    #[test] fn matrix_determinant_5()  { matrix_determinant_impl(5); }
+    #[test] fn matrix_determinant_10() { matrix_determinant_impl(10); }
+
+    #[test]
+    fn matrix_determinant_matches_matrix() {
+        let a = Matrix::<4>::from_fn(|_| thread_rng().gen_range(-1.0..1.0));
+        let b = DMatrix::from_fn(4, |index| a[index]);
+        assert!((a.determinant() - b.determinant()).abs() < 1e-9);
+    }
+}
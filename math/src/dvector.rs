@@ -0,0 +1,106 @@
+use std::ops::Index;
+use std::ops::IndexMut;
+
+use std::ops::Add;
+use std::ops::Sub;
+use std::ops::Mul;
+use std::ops::Div;
+
+use std::ops::AddAssign;
+use std::ops::SubAssign;
+use std::ops::MulAssign;
+use std::ops::DivAssign;
+
+/// Vector whose dimension is only known at runtime.
+///
+/// This is the heap-allocated counterpart of [crate::vector::Vector]. Binary operations between
+/// vectors of different dimensions panic.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DVector(Vec<f64>);
+
+impl DVector {
+    pub fn from_vec(values : Vec<f64>) -> Self {
+        Self(values)
+    }
+
+    pub fn from_slice(values : &[f64]) -> Self {
+        Self(values.to_vec())
+    }
+
+    pub fn from_fn<F>(dimension : usize, cb : F) -> Self
+    where
+        F: FnMut(usize) -> f64
+    {
+        Self((0..dimension).map(cb).collect())
+    }
+
+    pub fn into_vec(self) -> Vec<f64> {
+        self.0
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.0
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [f64] {
+        &mut self.0
+    }
+
+    /// Dimension of the vector.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Index<usize> for DVector {
+    type Output = f64;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for DVector {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl Add<DVector> for DVector { type Output = Self; fn add(mut self, rhs: Self) -> Self::Output { self += rhs; self } }
+impl Sub<DVector> for DVector { type Output = Self; fn sub(mut self, rhs: Self) -> Self::Output { self -= rhs; self } }
+impl Mul<DVector> for DVector { type Output = Self; fn mul(mut self, rhs: Self) -> Self::Output { self *= rhs; self } }
+impl Div<DVector> for DVector { type Output = Self; fn div(mut self, rhs: Self) -> Self::Output { self /= rhs; self } }
+
+impl Add<f64> for DVector { type Output = Self; fn add(mut self, rhs: f64) -> Self::Output { self += rhs; self } }
+impl Sub<f64> for DVector { type Output = Self; fn sub(mut self, rhs: f64) -> Self::Output { self -= rhs; self } }
+impl Mul<f64> for DVector { type Output = Self; fn mul(mut self, rhs: f64) -> Self::Output { self *= rhs; self } }
+impl Div<f64> for DVector { type Output = Self; fn div(mut self, rhs: f64) -> Self::Output { self /= rhs; self } }
+
+impl AddAssign<DVector> for DVector { fn add_assign(&mut self, rhs: Self) { assert_eq!(self.len(), rhs.len()); self.0.iter_mut().zip(rhs.0).for_each(|(a, b)| { *a += b }) } }
+impl SubAssign<DVector> for DVector { fn sub_assign(&mut self, rhs: Self) { assert_eq!(self.len(), rhs.len()); self.0.iter_mut().zip(rhs.0).for_each(|(a, b)| { *a -= b }) } }
+impl MulAssign<DVector> for DVector { fn mul_assign(&mut self, rhs: Self) { assert_eq!(self.len(), rhs.len()); self.0.iter_mut().zip(rhs.0).for_each(|(a, b)| { *a *= b }) } }
+impl DivAssign<DVector> for DVector { fn div_assign(&mut self, rhs: Self) { assert_eq!(self.len(), rhs.len()); self.0.iter_mut().zip(rhs.0).for_each(|(a, b)| { *a /= b }) } }
+
+impl AddAssign<f64> for DVector { fn add_assign(&mut self, rhs: f64) { self.0.iter_mut().for_each(|a| { *a += rhs }) } }
+impl SubAssign<f64> for DVector { fn sub_assign(&mut self, rhs: f64) { self.0.iter_mut().for_each(|a| { *a -= rhs }) } }
+impl MulAssign<f64> for DVector { fn mul_assign(&mut self, rhs: f64) { self.0.iter_mut().for_each(|a| { *a *= rhs }) } }
+impl DivAssign<f64> for DVector { fn div_assign(&mut self, rhs: f64) { self.0.iter_mut().for_each(|a| { *a /= rhs }) } }
+
+impl DVector {
+    pub fn zero(dimension : usize) -> Self {
+        Self(vec![0.0; dimension])
+    }
+}
+
+impl DVector {
+    pub fn squared_length(&self) -> f64 {
+        self.0.iter().map(|x| x * x).sum()
+    }
+
+    pub fn length(&self) -> f64 {
+        self.squared_length().sqrt()
+    }
+}
@@ -44,3 +44,46 @@ impl<const N: usize> InnerProduct<Vector<N>> for Vector<N> {
     }
 }
 
+impl InnerProduct<DMatrix> for DMatrix {
+    type Output = DMatrix;
+    fn inner_product(self, other : DMatrix) -> Self::Output {
+        assert_eq!(self.dimension(), other.dimension());
+        let n = self.dimension();
+        let mut result = Self::Output::zero(n);
+        for j in 0..n {
+            for i in 0..n {
+                for k in 0..n {
+                    result[(j, i)] += self[(j, k)] * other[(k, i)]
+                }
+            }
+        }
+        result
+    }
+}
+
+impl InnerProduct<DVector> for DMatrix {
+    type Output = DVector;
+    fn inner_product(self, other : DVector) -> Self::Output {
+        assert_eq!(self.dimension(), other.len());
+        let n = self.dimension();
+        let mut result = Self::Output::zero(n);
+        for j in 0..n {
+            for k in 0..n {
+                result[j] += self[(j, k)] * other[k]
+            }
+        }
+        result
+    }
+}
+
+impl InnerProduct<DVector> for DVector {
+    type Output = f64;
+    fn inner_product(self, other : DVector) -> Self::Output {
+        assert_eq!(self.len(), other.len());
+        let mut result = 0.0;
+        for k in 0..self.len() {
+            result += self[k] * other[k]
+        }
+        result
+    }
+}
@@ -1,26 +1,40 @@
-#![feature(coroutines)]
-#![feature(iter_from_coroutine)]
-#![feature(type_alias_impl_trait)]
-
 pub mod vector;
 pub mod matrix;
 
+pub mod dvector;
+pub mod dmatrix;
+
+pub mod point;
+
+pub mod lu;
+pub mod cholesky;
+
 pub mod inner_product;
 pub mod outer_product;
 
 pub mod multivariate_gaussian;
 pub mod mse;
+pub mod log_sum_exp;
 
 pub mod prelude {
     pub use crate::vector::Vector;
     pub use crate::matrix::Matrix;
 
+    pub use crate::dvector::DVector;
+    pub use crate::dmatrix::DMatrix;
+
+    pub use crate::point::Point;
+    pub use crate::point::PointMatrix;
+
+    pub use crate::lu::Lu;
+    pub use crate::cholesky::Cholesky;
+
     pub use crate::inner_product::InnerProduct;
     pub use crate::outer_product::OuterProduct;
 
     pub use crate::multivariate_gaussian::MultivariateGaussian;
     pub use crate::mse::MseIteratorExt;
+    pub use crate::log_sum_exp::LogSumExpIteratorExt;
 }
 
 mod array_zip;
-mod permutation;
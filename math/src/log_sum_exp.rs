@@ -0,0 +1,54 @@
+pub trait LogSumExpIteratorExt {
+    /// Compute ln(sum(exp(x))) without overflow or underflow.
+    ///
+    /// This is done in a single pass by keeping track of the running maximum and rescaling the
+    /// partial sum whenever it changes. Return negative infinity for an empty iterator, which
+    /// corresponds to ln(0).
+    fn log_sum_exp(self) -> f64;
+}
+
+impl<I> LogSumExpIteratorExt for I
+where
+    I: Iterator<Item = f64>
+{
+    fn log_sum_exp(self) -> f64 {
+        let mut max = f64::NEG_INFINITY;
+        let mut sum = 0.0;
+        for x in self {
+            if x == f64::NEG_INFINITY {
+                continue;
+            }
+
+            if x <= max {
+                sum += (x - max).exp();
+            } else {
+                sum = sum * (max - x).exp() + 1.0;
+                max = x;
+            }
+        }
+        max + sum.ln()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!([].into_iter().log_sum_exp(), f64::NEG_INFINITY);
+        assert_eq!([f64::NEG_INFINITY, f64::NEG_INFINITY].into_iter().log_sum_exp(), f64::NEG_INFINITY);
+
+        let values = [0.5f64, -1.0, 2.0, 0.0];
+        let expected = values.iter().map(|x| x.exp()).sum::<f64>().ln();
+        assert!((values.into_iter().log_sum_exp() - expected).abs() < 1e-12);
+
+        // Naive computation underflow to ln(0) here.
+        let values = [-1000.0, -1000.0];
+        assert!((values.into_iter().log_sum_exp() - (-1000.0 + 2.0f64.ln())).abs() < 1e-9);
+
+        // Naive computation overflow to ln(inf) here.
+        let values = [1000.0, 1000.0];
+        assert!((values.into_iter().log_sum_exp() - (1000.0 + 2.0f64.ln())).abs() < 1e-9);
+    }
+}
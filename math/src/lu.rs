@@ -0,0 +1,193 @@
+use crate::prelude::*;
+
+/// Error returned when attempting to factorize or invert a singular matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SingularMatrixError;
+
+impl std::fmt::Display for SingularMatrixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "matrix is singular")
+    }
+}
+
+impl std::error::Error for SingularMatrixError {}
+
+/// LU decomposition with partial pivoting.
+///
+/// We have PA = LU where P is a permutation matrix, L is unit lower triangular and U is upper
+/// triangular. Both L and U are stored packed in a single matrix, with the unit diagonal of L
+/// left implicit.
+#[derive(Debug, Clone)]
+pub struct Lu<M: PointMatrix> {
+    lu : M,
+    permutation : Vec<usize>,
+    sign : f64,
+}
+
+impl<M: PointMatrix> Lu<M> {
+    /// Factorize matrix.
+    ///
+    /// A pivot is considered to be zero if its magnitude is below n * epsilon times the largest
+    /// magnitude of any entry in the matrix, in which case the matrix is reported as singular.
+    pub fn new(matrix : &M) -> Result<Self, SingularMatrixError> {
+        let n = matrix.dimension();
+
+        let mut lu = matrix.clone();
+        let mut permutation = (0..n).collect::<Vec<_>>();
+        let mut sign = 1.0;
+
+        let scale = matrix.as_slice().iter().fold(0.0f64, |acc, x| acc.max(x.abs()));
+        let tolerance = scale * n as f64 * f64::EPSILON;
+
+        for pivot in 0..n {
+            let row = (pivot..n).max_by(|&a, &b| f64::total_cmp(&lu[(a, pivot)].abs(), &lu[(b, pivot)].abs())).unwrap();
+            if lu[(row, pivot)].abs() <= tolerance {
+                return Err(SingularMatrixError);
+            }
+
+            if row != pivot {
+                for i in 0..n {
+                    let tmp = lu[(row, i)];
+                    lu[(row, i)] = lu[(pivot, i)];
+                    lu[(pivot, i)] = tmp;
+                }
+                permutation.swap(row, pivot);
+                sign = -sign;
+            }
+
+            let c = lu[(pivot, pivot)];
+            for j in pivot+1..n {
+                let l = lu[(j, pivot)] / c;
+                lu[(j, pivot)] = l;
+                for i in pivot+1..n {
+                    lu[(j, i)] -= l * lu[(pivot, i)];
+                }
+            }
+        }
+
+        Ok(Self { lu, permutation, sign })
+    }
+
+    pub fn determinant(&self) -> f64 {
+        (0..self.lu.dimension()).map(|i| self.lu[(i, i)]).product::<f64>() * self.sign
+    }
+
+    /// Natural logarithm of the absolute value of the determinant.
+    ///
+    /// This does not overflow or underflow for large matrices unlike [Self::determinant].
+    pub fn log_determinant(&self) -> f64 {
+        (0..self.lu.dimension()).map(|i| self.lu[(i, i)].abs().ln()).sum::<f64>()
+    }
+
+    /// Solve Ax = b for x.
+    pub fn solve(&self, b : &M::Point) -> M::Point {
+        let n = self.lu.dimension();
+        assert_eq!(n, b.dimension());
+
+        // 1: Forward substitution Ly = Pb
+        let mut x = M::Point::zero(n);
+        for j in 0..n {
+            let mut value = b[self.permutation[j]];
+            for i in 0..j {
+                value -= self.lu[(j, i)] * x[i];
+            }
+            x[j] = value;
+        }
+
+        // 2: Backward substitution Ux = y
+        for j in (0..n).rev() {
+            let mut value = x[j];
+            for i in j+1..n {
+                value -= self.lu[(j, i)] * x[i];
+            }
+            x[j] = value / self.lu[(j, j)];
+        }
+
+        x
+    }
+
+    pub fn inverse(&self) -> M {
+        let n = self.lu.dimension();
+
+        let mut result = M::zero(n);
+        for i in 0..n {
+            let mut e = M::Point::zero(n);
+            e[i] = 1.0;
+
+            let column = self.solve(&e);
+            for j in 0..n {
+                result[(j, i)] = column[j];
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+
+    fn random_matrix(dimension : usize) -> DMatrix {
+        DMatrix::from_fn(dimension, |_| thread_rng().gen_range(-1.0..1.0))
+    }
+
+    #[test]
+    fn solve() {
+        for dimension in [1, 2, 5, 12, 30] {
+            let a = random_matrix(dimension);
+            let x = DVector::from_fn(dimension, |_| thread_rng().gen_range(-1.0..1.0));
+            let b = a.clone().inner_product(x.clone());
+
+            let result = Lu::new(&a).unwrap().solve(&b);
+            for i in 0..dimension {
+                assert!((result[i] - x[i]).abs() < 1e-6, "dimension = {dimension}, result = {result:?}, expected = {x:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn determinant() {
+        let a = Matrix::from_array([
+            [2.0, 1.0, 0.0],
+            [1.0, 3.0, 1.0],
+            [0.0, 1.0, 4.0],
+        ]);
+        let lu = Lu::new(&a).unwrap();
+        assert!((lu.determinant() - 18.0).abs() < 1e-12);
+        assert!((lu.log_determinant() - 18.0f64.ln()).abs() < 1e-12);
+
+        // Permuting two rows flip the sign of determinant.
+        let b = Matrix::from_array([
+            [1.0, 3.0, 1.0],
+            [2.0, 1.0, 0.0],
+            [0.0, 1.0, 4.0],
+        ]);
+        assert!((Lu::new(&b).unwrap().determinant() + 18.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn log_determinant_large() {
+        // Determinant of 2I is 2^400 which overflows f64 but its logarithm does not.
+        let a = DMatrix::one(400) * 2.0;
+        let lu = Lu::new(&a).unwrap();
+        assert!((lu.log_determinant() - 400.0 * 2.0f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn singular() {
+        let a = Matrix::from_array([
+            [1.0, 2.0, 3.0],
+            [2.0, 4.0, 6.0],
+            [1.0, 0.0, 1.0],
+        ]);
+        assert_eq!(Lu::new(&a).err(), Some(SingularMatrixError));
+        assert_eq!(a.try_inverse().err(), Some(SingularMatrixError));
+        assert_eq!(a.determinant(), 0.0);
+
+        let a = DMatrix::zero(3);
+        assert_eq!(a.try_inverse().err(), Some(SingularMatrixError));
+        assert_eq!(a.determinant(), 0.0);
+    }
+}
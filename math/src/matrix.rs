@@ -1,6 +1,6 @@
 use crate::array_zip::ArrayZip;
-use crate::permutation::Parity;
-use crate::permutation::Permutations;
+use crate::lu::Lu;
+use crate::lu::SingularMatrixError;
 
 use std::ops::Index;
 use std::ops::IndexMut;
@@ -42,6 +42,11 @@ impl<const N: usize> Matrix<N> {
         self.0
     }
 
+    /// Values in row-major order.
+    pub fn as_slice(&self) -> &[f64] {
+        self.0.as_flattened()
+    }
+
     pub fn each_ref(&self) -> [[&f64; N]; N] {
         self.0.each_ref().map(|x| x.each_ref())
     }
@@ -74,15 +79,15 @@ impl<const N: usize> Sub<f64> for Matrix<N> { type Output = Self; fn sub(self, r
 impl<const N: usize> Mul<f64> for Matrix<N> { type Output = Self; fn mul(self, rhs: f64) -> Self::Output { Matrix(self.into_array().map(|a| a.map(|a| a * rhs))) } }
 impl<const N: usize> Div<f64> for Matrix<N> { type Output = Self; fn div(self, rhs: f64) -> Self::Output { Matrix(self.into_array().map(|a| a.map(|a| a / rhs))) } }
 
-impl<const N: usize> AddAssign<Matrix<N>> for Matrix<N> { fn add_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).map(|(a, b)| a.zip(b).map(|(a, b)| *a += b)); } }
-impl<const N: usize> SubAssign<Matrix<N>> for Matrix<N> { fn sub_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).map(|(a, b)| a.zip(b).map(|(a, b)| *a -= b)); } }
-impl<const N: usize> MulAssign<Matrix<N>> for Matrix<N> { fn mul_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).map(|(a, b)| a.zip(b).map(|(a, b)| *a *= b)); } }
-impl<const N: usize> DivAssign<Matrix<N>> for Matrix<N> { fn div_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).map(|(a, b)| a.zip(b).map(|(a, b)| *a /= b)); } }
+impl<const N: usize> AddAssign<Matrix<N>> for Matrix<N> { fn add_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).into_iter().for_each(|(a, b)| a.zip(b).into_iter().for_each(|(a, b)| *a += b)); } }
+impl<const N: usize> SubAssign<Matrix<N>> for Matrix<N> { fn sub_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).into_iter().for_each(|(a, b)| a.zip(b).into_iter().for_each(|(a, b)| *a -= b)); } }
+impl<const N: usize> MulAssign<Matrix<N>> for Matrix<N> { fn mul_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).into_iter().for_each(|(a, b)| a.zip(b).into_iter().for_each(|(a, b)| *a *= b)); } }
+impl<const N: usize> DivAssign<Matrix<N>> for Matrix<N> { fn div_assign(&mut self, rhs: Self) { self.each_mut().zip(rhs.into_array()).into_iter().for_each(|(a, b)| a.zip(b).into_iter().for_each(|(a, b)| *a /= b)); } }
 
-impl<const N: usize> AddAssign<f64> for Matrix<N> { fn add_assign(&mut self, rhs: f64) { self.each_mut().map(|a| a.map(|a| *a += rhs)); } }
-impl<const N: usize> SubAssign<f64> for Matrix<N> { fn sub_assign(&mut self, rhs: f64) { self.each_mut().map(|a| a.map(|a| *a -= rhs)); } }
-impl<const N: usize> MulAssign<f64> for Matrix<N> { fn mul_assign(&mut self, rhs: f64) { self.each_mut().map(|a| a.map(|a| *a *= rhs)); } }
-impl<const N: usize> DivAssign<f64> for Matrix<N> { fn div_assign(&mut self, rhs: f64) { self.each_mut().map(|a| a.map(|a| *a /= rhs)); } }
+impl<const N: usize> AddAssign<f64> for Matrix<N> { fn add_assign(&mut self, rhs: f64) { self.each_mut().into_iter().for_each(|a| a.into_iter().for_each(|a| *a += rhs)); } }
+impl<const N: usize> SubAssign<f64> for Matrix<N> { fn sub_assign(&mut self, rhs: f64) { self.each_mut().into_iter().for_each(|a| a.into_iter().for_each(|a| *a -= rhs)); } }
+impl<const N: usize> MulAssign<f64> for Matrix<N> { fn mul_assign(&mut self, rhs: f64) { self.each_mut().into_iter().for_each(|a| a.into_iter().for_each(|a| *a *= rhs)); } }
+impl<const N: usize> DivAssign<f64> for Matrix<N> { fn div_assign(&mut self, rhs: f64) { self.each_mut().into_iter().for_each(|a| a.into_iter().for_each(|a| *a /= rhs)); } }
 
 impl<const N: usize> Sum<Matrix<N>> for Matrix<N> {
     fn sum<I: Iterator<Item = Matrix<N>>>(iter: I) -> Self {
@@ -105,50 +110,21 @@ impl<const N: usize> Matrix<N> {
 }
 
 impl<const N: usize> Matrix<N> {
+    /// Inverse computed by LU decomposition.
+    ///
+    /// Panic if the matrix is singular. See [Self::try_inverse] for a fallible alternative.
     pub fn inverse(self) -> Self {
-        let mut lhs = self;
-        let mut rhs = Self::one();
-
-        for pivot in 0..N {
-            let row = (pivot..N).find(|&row| lhs[(row, pivot)] != 0.0).unwrap();
-            if row != pivot {
-                for i in 0..N {
-                    unsafe {
-                        std::ptr::swap(&mut lhs[(row, i)], &mut lhs[(pivot, i)]);
-                        std::ptr::swap(&mut rhs[(row, i)], &mut rhs[(pivot, i)]);
-                    }
-                }
-            }
-
-            let c = lhs[(pivot, pivot)];
-            lhs[(pivot, pivot)] = 1.0;
-
-            for i in pivot+1..N { lhs[(pivot, i)] /= c; }
-            for i in 0..N       { rhs[(pivot, i)] /= c; }
-
-            for j in 0..N {
-                if j != pivot {
-                    let c = lhs[(j, pivot)];
-                    lhs[(j, pivot)] = 0.0;
-
-                    for i in pivot+1..N { lhs[(j, i)] -= c * lhs[(pivot, i)]; }
-                    for i in 0..N       { rhs[(j, i)] -= c * rhs[(pivot, i)]; }
-                }
-            }
-        }
+        self.try_inverse().expect("matrix is singular")
+    }
 
-        rhs
+    /// Inverse computed by LU decomposition.
+    pub fn try_inverse(self) -> Result<Self, SingularMatrixError> {
+        Ok(Lu::new(&self)?.inverse())
     }
 
+    /// Determinant computed by LU decomposition.
     pub fn determinant(self) -> f64 {
-        let mut result = 0.0;
-        for (parity, permutation) in Permutations::<N>::new() {
-            match parity {
-                Parity::Even => result += permutation.into_iter().enumerate().map(|indices| self[indices]).product::<f64>(),
-                Parity::Odd  => result -= permutation.into_iter().enumerate().map(|indices| self[indices]).product::<f64>(),
-            }
-        }
-        result
+        Lu::new(&self).map_or(0.0, |lu| lu.determinant())
     }
 }
 
@@ -197,19 +173,19 @@ mod tests {
         eprintln!("Matrix a:");
         eprintln!("=====================");
         eprintln!("{a}");
-        eprintln!("");
+        eprintln!();
 
         eprintln!("=====================");
         eprintln!("Matrix b:");
         eprintln!("=====================");
         eprintln!("{b}");
-        eprintln!("");
+        eprintln!();
 
         eprintln!("=====================");
         eprintln!("Matrix a * b");
         eprintln!("=====================");
         eprintln!("{result}");
-        eprintln!("");
+        eprintln!();
 
         for j in 0..N {
             for i in 0..N {
@@ -237,19 +213,19 @@ mod tests {
         eprintln!("Matrix a:");
         eprintln!("=====================");
         eprintln!("{a}");
-        eprintln!("");
+        eprintln!();
 
         eprintln!("=====================");
         eprintln!("Matrix b:");
         eprintln!("=====================");
         eprintln!("{b}");
-        eprintln!("");
+        eprintln!();
 
         eprintln!("=====================");
         eprintln!("det(a)          = {det_a}");
         eprintln!("det(b)          = {det_b}");
         eprintln!("det(a) * det(b) = {result}");
-        eprintln!("");
+        eprintln!();
 
         assert!((result - expected) < 1e-5)
     }
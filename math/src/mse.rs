@@ -1,6 +1,9 @@
 use crate::prelude::*;
 
-trait Mse: Sized {
+/// Mean of squares of all components of a sequence of values.
+///
+/// This is typically applied to differences between two sequences to measure how far apart they are.
+pub trait Mse: Sized {
     fn mse<I>(iter: I) -> f64
        where I: Iterator<Item = Self>;
 }
@@ -10,7 +13,7 @@ impl Mse for f64 {
        where I: Iterator<Item = Self>
     {
         let mut count = 0usize;
-        let total = iter.inspect(|_| count += 1).sum::<f64>();
+        let total = iter.inspect(|_| count += 1).map(|x| x * x).sum::<f64>();
         total / count as f64
     }
 }
@@ -20,7 +23,7 @@ impl<const N: usize> Mse for Vector<N> {
        where I: Iterator<Item = Self>
     {
         let mut count = 0usize;
-        let total = iter.map(|x| x.into_array()).flatten().inspect(|_| count += 1).sum::<f64>();
+        let total = iter.flat_map(|x| x.into_array()).inspect(|_| count += 1).map(|x| x * x).sum::<f64>();
         total / count as f64
     }
 }
@@ -30,7 +33,27 @@ impl<const N: usize> Mse for Matrix<N> {
        where I: Iterator<Item = Self>
     {
         let mut count = 0usize;
-        let total = iter.map(|x| x.into_array()).flatten().flatten().inspect(|_| count += 1).sum::<f64>();
+        let total = iter.flat_map(|x| x.into_array()).flatten().inspect(|_| count += 1).map(|x| x * x).sum::<f64>();
+        total / count as f64
+    }
+}
+
+impl Mse for DVector {
+    fn mse<I>(iter: I) -> f64
+       where I: Iterator<Item = Self>
+    {
+        let mut count = 0usize;
+        let total = iter.flat_map(|x| x.into_vec()).inspect(|_| count += 1).map(|x| x * x).sum::<f64>();
+        total / count as f64
+    }
+}
+
+impl Mse for DMatrix {
+    fn mse<I>(iter: I) -> f64
+       where I: Iterator<Item = Self>
+    {
+        let mut count = 0usize;
+        let total = iter.flat_map(|x| x.into_vec()).inspect(|_| count += 1).map(|x| x * x).sum::<f64>();
         total / count as f64
     }
 }
@@ -48,3 +71,15 @@ where
         Mse::mse(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mse() {
+        assert_eq!([1.0, -1.0, 2.0, -2.0].into_iter().mse(), 2.5);
+        assert_eq!([Vector::from_array([3.0, -4.0])].into_iter().mse(), 12.5);
+        assert_eq!([DVector::from_vec(vec![3.0]), DVector::from_vec(vec![-4.0])].into_iter().mse(), 12.5);
+    }
+}
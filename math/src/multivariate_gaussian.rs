@@ -1,24 +1,75 @@
 use crate::prelude::*;
 
-pub struct MultivariateGaussian<const N: usize> {
-    mean : Vector<N>,
-    bilinear_form : Matrix<N>,
-    normalizing_factor : f64,
+pub struct MultivariateGaussian<P: Point> {
+    mean : P,
+    bilinear_form : P::Matrix,
+    log_normalizing_factor : f64,
 }
 
-impl<const N: usize> MultivariateGaussian<N> {
-    pub fn new(mean : Vector<N>, covariance : Matrix<N>) -> Self {
-        let covariance_inv = covariance.inverse();
-        let covariance_det = covariance.determinant();
+impl<P: Point> MultivariateGaussian<P> {
+    /// Constructor.
+    ///
+    /// The covariance matrix is factorized by Cholesky decomposition, falling back to LU
+    /// decomposition if it is not positive-definite due to rounding errors.
+    ///
+    /// Panic if the covariance matrix is singular.
+    pub fn new(mean : P, covariance : P::Matrix) -> Self {
+        let (covariance_inv, covariance_log_det) = match Cholesky::new(&covariance) {
+            Ok(cholesky) => (cholesky.inverse(), cholesky.log_determinant()),
+            Err(_) => {
+                let lu = Lu::new(&covariance).expect("covariance matrix is singular");
+                (lu.inverse(), lu.log_determinant())
+            }
+        };
 
         let bilinear_form = covariance_inv * -0.5;
-        let normalizing_factor = 1.0 / ((2.0 * std::f64::consts::PI).powi(N as i32) * covariance_det).sqrt();
+        let log_normalizing_factor = -0.5 * (mean.dimension() as f64 * (2.0 * std::f64::consts::PI).ln() + covariance_log_det);
 
-        Self { mean, bilinear_form, normalizing_factor, }
+        Self { mean, bilinear_form, log_normalizing_factor, }
     }
 
-    pub fn sample(&self, point : Vector<N>) -> f64 {
-        let displacement = point - self.mean;
-        self.bilinear_form.inner_product(displacement).inner_product(displacement).exp() * self.normalizing_factor
+    /// Distribution with the same covariance matrix but a different mean, which reuses the
+    /// factorization of the covariance matrix.
+    pub fn with_mean(&self, mean : P) -> Self {
+        assert_eq!(self.mean.dimension(), mean.dimension());
+        Self { mean, bilinear_form : self.bilinear_form.clone(), log_normalizing_factor : self.log_normalizing_factor, }
+    }
+
+    /// Probability density at point.
+    ///
+    /// This underflows to zero for points far away from the mean, especially in high dimensions.
+    /// Prefer [Self::log_pdf] where possible.
+    pub fn sample(&self, point : &P) -> f64 {
+        self.log_pdf(point).exp()
+    }
+
+    /// Natural logarithm of probability density at point.
+    pub fn log_pdf(&self, point : &P) -> f64 {
+        let displacement = point.clone() - self.mean.clone();
+        self.bilinear_form.transform(&displacement).dot(&displacement) + self.log_normalizing_factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_pdf() {
+        // Standard normal distribution
+        let dist = MultivariateGaussian::new(Vector::from_array([0.0]), Matrix::one());
+        assert!((dist.log_pdf(&Vector::from_array([0.0])) - -0.5 * (2.0 * std::f64::consts::PI).ln()).abs() < 1e-12);
+        assert!((dist.log_pdf(&Vector::from_array([2.0])) - (-2.0 - 0.5 * (2.0 * std::f64::consts::PI).ln())).abs() < 1e-12);
+
+        // Density underflow but log density remain finite.
+        let dist = MultivariateGaussian::new(DVector::zero(50), DMatrix::one(50) * 0.01);
+        let point = DVector::from_fn(50, |_| 10.0);
+        assert_eq!(dist.sample(&point), 0.0);
+        assert!(dist.log_pdf(&point).is_finite());
+
+        // Shifting the mean shifts the density.
+        let dist = MultivariateGaussian::new(Vector::from_array([0.0, 0.0]), Matrix::from_array([[2.0, 0.5], [0.5, 1.0]]));
+        let shifted = dist.with_mean(Vector::from_array([1.0, -1.0]));
+        assert!((shifted.log_pdf(&Vector::from_array([1.5, -0.5])) - dist.log_pdf(&Vector::from_array([0.5, 0.5]))).abs() < 1e-12);
     }
 }
@@ -18,3 +18,17 @@ impl<const N: usize> OuterProduct<Vector<N>> for Vector<N> {
     }
 }
 
+impl OuterProduct<DVector> for DVector {
+    type Output = DMatrix;
+    fn outer_product(self, other : DVector) -> Self::Output {
+        assert_eq!(self.len(), other.len());
+        let n = self.len();
+        let mut result = DMatrix::zero(n);
+        for j in 0..n {
+            for i in 0..n {
+                result[(j, i)] = self[j] * other[i];
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,216 @@
+use crate::prelude::*;
+use crate::mse::Mse;
+use crate::lu::Lu;
+use crate::lu::SingularMatrixError;
+
+use std::fmt::Debug;
+
+use std::ops::Index;
+use std::ops::IndexMut;
+
+use std::ops::Add;
+use std::ops::Sub;
+use std::ops::Mul;
+use std::ops::Div;
+
+use std::ops::AddAssign;
+use std::ops::SubAssign;
+use std::ops::MulAssign;
+use std::ops::DivAssign;
+
+/// Common interface of [Vector] and [DVector].
+///
+/// Algorithms written against this trait work both for points whose dimension is known at compile
+/// time and for points whose dimension is only known at runtime. Since [DVector] is not [Copy],
+/// operations that would otherwise consume their operands take them by reference instead.
+pub trait Point: Clone + Debug + Send + Sync
+    + Index<usize, Output = f64> + IndexMut<usize>
+    + Add<Output = Self> + Sub<Output = Self> + Mul<f64, Output = Self> + Div<f64, Output = Self>
+    + AddAssign + SubAssign + MulAssign<f64> + DivAssign<f64>
+    + Mse
+{
+    /// Square matrix type of matching dimension.
+    type Matrix: PointMatrix<Point = Self>;
+
+    /// Zero vector of given dimension.
+    ///
+    /// For types whose dimension is known at compile time, the dimension must match.
+    fn zero(dimension : usize) -> Self;
+
+    fn dimension(&self) -> usize;
+    fn as_slice(&self) -> &[f64];
+
+    fn dot(&self, other : &Self) -> f64 {
+        assert_eq!(self.dimension(), other.dimension());
+        std::iter::zip(self.as_slice(), other.as_slice()).map(|(a, b)| a * b).sum()
+    }
+
+    fn outer(&self, other : &Self) -> Self::Matrix {
+        assert_eq!(self.dimension(), other.dimension());
+        Self::Matrix::from_fn(self.dimension(), |(j, i)| self[j] * other[i])
+    }
+
+    /// Squared euclidean distance.
+    ///
+    /// This does not allocate unlike computing (self - other).squared_length().
+    fn squared_distance(&self, other : &Self) -> f64 {
+        assert_eq!(self.dimension(), other.dimension());
+        std::iter::zip(self.as_slice(), other.as_slice()).map(|(a, b)| (a - b) * (a - b)).sum()
+    }
+
+    /// Euclidean distance.
+    fn distance(&self, other : &Self) -> f64 {
+        self.squared_distance(other).sqrt()
+    }
+}
+
+/// Common interface of [Matrix] and [DMatrix].
+pub trait PointMatrix: Clone + Debug + Send + Sync
+    + Index<(usize, usize), Output = f64> + IndexMut<(usize, usize)>
+    + Add<Output = Self> + Sub<Output = Self> + Mul<f64, Output = Self> + Div<f64, Output = Self>
+    + AddAssign + SubAssign + MulAssign<f64> + DivAssign<f64>
+    + Mse
+{
+    /// Vector type of matching dimension.
+    type Point: Point<Matrix = Self>;
+
+    /// Zero matrix of given dimension.
+    ///
+    /// For types whose dimension is known at compile time, the dimension must match.
+    fn zero(dimension : usize) -> Self;
+
+    /// Identity matrix of given dimension.
+    ///
+    /// For types whose dimension is known at compile time, the dimension must match.
+    fn one(dimension : usize) -> Self;
+
+    fn from_fn<F>(dimension : usize, cb : F) -> Self
+    where
+        F: FnMut((usize, usize)) -> f64;
+
+    fn dimension(&self) -> usize;
+    fn as_slice(&self) -> &[f64];
+
+    /// Matrix-vector product.
+    fn transform(&self, point : &Self::Point) -> Self::Point {
+        assert_eq!(self.dimension(), point.dimension());
+        let mut result = Self::Point::zero(self.dimension());
+        for j in 0..self.dimension() {
+            for k in 0..self.dimension() {
+                result[j] += self[(j, k)] * point[k];
+            }
+        }
+        result
+    }
+
+    /// Inverse computed by LU decomposition.
+    ///
+    /// Panic if the matrix is singular. See [Self::try_inverse] for a fallible alternative.
+    fn inverse(&self) -> Self {
+        self.try_inverse().expect("matrix is singular")
+    }
+
+    /// Inverse computed by LU decomposition.
+    fn try_inverse(&self) -> Result<Self, SingularMatrixError> {
+        Ok(Lu::new(self)?.inverse())
+    }
+
+    /// Determinant computed by LU decomposition.
+    fn determinant(&self) -> f64 {
+        Lu::new(self).map_or(0.0, |lu| lu.determinant())
+    }
+}
+
+impl<const N: usize> Point for Vector<N> {
+    type Matrix = Matrix<N>;
+
+    fn zero(dimension : usize) -> Self {
+        assert_eq!(dimension, N);
+        Vector::zero()
+    }
+
+    fn dimension(&self) -> usize {
+        N
+    }
+
+    fn as_slice(&self) -> &[f64] {
+        Vector::as_slice(self)
+    }
+}
+
+impl<const N: usize> PointMatrix for Matrix<N> {
+    type Point = Vector<N>;
+
+    fn zero(dimension : usize) -> Self {
+        assert_eq!(dimension, N);
+        Matrix::zero()
+    }
+
+    fn one(dimension : usize) -> Self {
+        assert_eq!(dimension, N);
+        Matrix::one()
+    }
+
+    fn from_fn<F>(dimension : usize, cb : F) -> Self
+    where
+        F: FnMut((usize, usize)) -> f64
+    {
+        assert_eq!(dimension, N);
+        Matrix::from_fn(cb)
+    }
+
+    fn dimension(&self) -> usize {
+        N
+    }
+
+    fn as_slice(&self) -> &[f64] {
+        Matrix::as_slice(self)
+    }
+
+    fn transform(&self, point : &Vector<N>) -> Vector<N> {
+        self.inner_product(*point)
+    }
+}
+
+impl Point for DVector {
+    type Matrix = DMatrix;
+
+    fn zero(dimension : usize) -> Self {
+        DVector::zero(dimension)
+    }
+
+    fn dimension(&self) -> usize {
+        self.len()
+    }
+
+    fn as_slice(&self) -> &[f64] {
+        DVector::as_slice(self)
+    }
+}
+
+impl PointMatrix for DMatrix {
+    type Point = DVector;
+
+    fn zero(dimension : usize) -> Self {
+        DMatrix::zero(dimension)
+    }
+
+    fn one(dimension : usize) -> Self {
+        DMatrix::one(dimension)
+    }
+
+    fn from_fn<F>(dimension : usize, cb : F) -> Self
+    where
+        F: FnMut((usize, usize)) -> f64
+    {
+        DMatrix::from_fn(dimension, cb)
+    }
+
+    fn dimension(&self) -> usize {
+        DMatrix::dimension(self)
+    }
+
+    fn as_slice(&self) -> &[f64] {
+        DMatrix::as_slice(self)
+    }
+}
@@ -34,6 +34,10 @@ impl<const N: usize> Vector<N> {
         self.0
     }
 
+    pub fn as_slice(&self) -> &[f64] {
+        &self.0
+    }
+
     pub fn each_ref(&self) -> [&f64; N] {
         self.0.each_ref()
     }
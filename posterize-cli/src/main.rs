@@ -9,6 +9,7 @@ use anyhow::Result;
 use anyhow::Context;
 
 use clap::Parser;
+use rand::prelude::*;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -17,6 +18,9 @@ struct Cli {
     input : PathBuf,
     /// Output filepath
     output : PathBuf,
+    /// Seed for random number generator, so that output is reproducible
+    #[arg(long)]
+    seed : Option<u64>,
     /// Posterize method
     #[command(subcommand)]
     method : PosterizeMethod,
@@ -34,7 +38,10 @@ fn main() -> Result<()> {
         .map(Vector::from_array)
         .collect::<Vec<_>>();
 
-    cli.method.posterize(&mut samples);
+    match cli.seed {
+        Some(seed) => cli.method.posterize_with_seed(&mut samples, seed),
+        None => cli.method.posterize(&mut samples, &mut thread_rng()),
+    }
 
     let pixels = samples
         .into_iter()
@@ -1,5 +1,7 @@
 use posterize::PosterizeMethod;
 use posterize::ClusterInit;
+use posterize::CovarianceType;
+use posterize::EmptyClusterStrategy;
 
 use math::prelude::*;
 use frei0r_rs::*;
@@ -9,9 +11,16 @@ use std::ffi::CString;
 
 #[derive(PluginBase)]
 pub struct PosterizePlugin {
-    #[frei0r(explain = c"clustering method to use(choices: k-means, gaussian-mixture, default : k-means)")] cluster_method : CString,
+    #[frei0r(explain = c"clustering method to use(choices: k-means, mini-batch-k-means, gaussian-mixture, default : k-means)")] cluster_method : CString,
     #[frei0r(explain = c"initialization method to use for clustering(choices: llyod, k-means++, default: llyod)")] cluster_init : CString,
     #[frei0r(explain = c"number of clusters(default: 2)")] cluster_count : f64,
+    #[frei0r(explain = c"seed for random number generator, identical seeds give identical output for identical frames(default: 0)")] seed : f64,
+    #[frei0r(explain = c"number of random initializations, keeping the best one(default: 1)")] restarts : f64,
+    #[frei0r(explain = c"number of pixels drawn in each iteration for mini-batch k-means(default: 1024)")] batch_size : f64,
+    #[frei0r(explain = c"lower bound on per-cluster learning rate for mini-batch k-means, in [0, 1](default: 0)")] min_learning_rate : f64,
+    #[frei0r(explain = c"what to do with empty clusters for k-means(choices: keep-previous, reseed-farthest, split-largest, drop, default: reseed-farthest)")] empty_cluster_strategy : CString,
+    #[frei0r(explain = c"covariance type for gaussian mixture(choices: full, diagonal, spherical, tied, default: full)")] covariance_type : CString,
+    #[frei0r(explain = c"value added to diagonal of covariance matrices for gaussian mixture(default: 0.000001)")] covariance_regularization : f64,
 }
 
 impl PosterizePlugin {
@@ -20,11 +29,23 @@ impl PosterizePlugin {
             Some(PosterizeMethod::KMeans {
                 cluster_init : self.init()?,
                 cluster_count : self.cluster_count()?,
+                empty_cluster_strategy : self.empty_cluster_strategy()?,
+                restarts : self.restarts()?,
+            })
+        } else if self.cluster_method.as_c_str() == c"mini-batch-k-means" {
+            Some(PosterizeMethod::MiniBatchKMeans {
+                cluster_init : self.init()?,
+                cluster_count : self.cluster_count()?,
+                batch_size : self.batch_size()?,
+                min_learning_rate : self.min_learning_rate()?,
             })
         } else if self.cluster_method.as_c_str() == c"gaussian-mixture" {
             Some(PosterizeMethod::GaussianMixture {
                 cluster_init : self.init()?,
                 cluster_count : self.cluster_count()?,
+                covariance_type : self.covariance_type()?,
+                covariance_regularization : self.covariance_regularization,
+                restarts : self.restarts()?,
             })
         } else {
             None
@@ -45,6 +66,46 @@ impl PosterizePlugin {
         NonZero::new(self.cluster_count as usize)
     }
 
+    fn batch_size(&self) -> Option<NonZero<usize>> {
+        NonZero::new(self.batch_size as usize)
+    }
+
+    fn min_learning_rate(&self) -> Option<f64> {
+        (0.0..=1.0).contains(&self.min_learning_rate).then_some(self.min_learning_rate)
+    }
+
+    fn restarts(&self) -> Option<NonZero<usize>> {
+        NonZero::new(self.restarts as usize)
+    }
+
+    fn empty_cluster_strategy(&self) -> Option<EmptyClusterStrategy> {
+        if self.empty_cluster_strategy.as_c_str() == c"keep-previous" {
+            Some(EmptyClusterStrategy::KeepPrevious)
+        } else if self.empty_cluster_strategy.as_c_str() == c"reseed-farthest" {
+            Some(EmptyClusterStrategy::ReseedFarthest)
+        } else if self.empty_cluster_strategy.as_c_str() == c"split-largest" {
+            Some(EmptyClusterStrategy::SplitLargest)
+        } else if self.empty_cluster_strategy.as_c_str() == c"drop" {
+            Some(EmptyClusterStrategy::Drop)
+        } else {
+            None
+        }
+    }
+
+    fn covariance_type(&self) -> Option<CovarianceType> {
+        if self.covariance_type.as_c_str() == c"full" {
+            Some(CovarianceType::Full)
+        } else if self.covariance_type.as_c_str() == c"diagonal" {
+            Some(CovarianceType::Diagonal)
+        } else if self.covariance_type.as_c_str() == c"spherical" {
+            Some(CovarianceType::Spherical)
+        } else if self.covariance_type.as_c_str() == c"tied" {
+            Some(CovarianceType::Tied)
+        } else {
+            None
+        }
+    }
+
 }
 
 impl Plugin for PosterizePlugin {
@@ -65,6 +126,13 @@ impl Plugin for PosterizePlugin {
             cluster_method : CString::from(c"k-means"),
             cluster_count : 2.0,
             cluster_init : CString::from(c"k-means++"),
+            restarts : 1.0,
+            batch_size : 1024.0,
+            min_learning_rate : 0.0,
+            seed : 0.0,
+            empty_cluster_strategy : CString::from(c"reseed-farthest"),
+            covariance_type : CString::from(c"full"),
+            covariance_regularization : 1e-6,
         }
     }
 
@@ -76,7 +144,7 @@ impl Plugin for PosterizePlugin {
             .collect::<Vec<_>>();
 
         let posterize_method = self.posterize_method().unwrap();
-        posterize_method.posterize(&mut samples);
+        posterize_method.posterize_with_seed(&mut samples, self.seed as u64);
 
         let samples = samples
             .into_iter()
@@ -3,6 +3,8 @@ use ui::UiExt;
 
 use posterize::PosterizeMethod;
 use posterize::ClusterInit;
+use posterize::CovarianceType;
+use posterize::EmptyClusterStrategy;
 
 use math::prelude::*;
 
@@ -18,6 +20,8 @@ use anyhow::Context;
 use std::path::PathBuf;
 use std::num::NonZero;
 
+use rand::prelude::*;
+
 /// Open a image.
 ///
 /// This is fast.
@@ -33,14 +37,17 @@ fn open_image(path : &PathBuf) -> Result<image::RgbImage> {
 /// Process a image by posterizing it.
 ///
 /// This takes a long time and need to be done in a separate thread.
-fn process_image(image : &image::RgbImage, method: PosterizeMethod) -> image::RgbImage {
+fn process_image(image : &image::RgbImage, method: PosterizeMethod, seed: Option<u64>) -> image::RgbImage {
     let mut samples = image
         .pixels()
         .map(|pixel| pixel.0.map(|subpixel| subpixel as f64))
         .map(Vector::from_array)
         .collect::<Vec<_>>();
 
-    method.posterize(&mut samples);
+    match seed {
+        Some(seed) => method.posterize_with_seed(&mut samples, seed),
+        None => method.posterize(&mut samples, &mut thread_rng()),
+    }
 
     let pixels = samples
         .into_iter()
@@ -65,6 +72,9 @@ fn upload_image(ctx: &egui::Context, image: &image::RgbImage) -> egui::TextureHa
 struct MyEguiApp {
     method: PosterizeMethod,
 
+    fixed_seed: bool,
+    seed: u64,
+
     path: Option<PathBuf>,
 
     input: Option<image::RgbImage>,
@@ -80,7 +90,11 @@ impl MyEguiApp {
             method : PosterizeMethod::KMeans {
                 cluster_init : ClusterInit::KMeanPlusPlus,
                 cluster_count : NonZero::new(3).unwrap(),
+                empty_cluster_strategy : EmptyClusterStrategy::ReseedFarthest,
+                restarts : NonZero::new(1).unwrap(),
             },
+            fixed_seed : false,
+            seed : 0,
             path: None,
             input : None,
             output : None,
@@ -99,7 +113,8 @@ impl MyEguiApp {
     }
 
     fn update_output(&mut self) {
-        self.output = self.input.as_ref().map(|input| process_image(input, self.method));
+        let seed = self.fixed_seed.then_some(self.seed);
+        self.output = self.input.as_ref().map(|input| process_image(input, self.method, seed));
     }
 
     fn update_input_texture(&mut self, ctx: &egui::Context) {
@@ -151,11 +166,19 @@ impl MyEguiApp {
             // Separator
             ui.separator();
 
+            // Seed
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.fixed_seed, "Fixed seed");
+                ui.add_enabled(self.fixed_seed, egui::DragValue::new(&mut self.seed));
+            });
+
             // Select method
             egui::ComboBox::from_label("Method")
                 .selected_text(match self.method {
                     PosterizeMethod::KMeans { .. } => "k means",
+                    PosterizeMethod::MiniBatchKMeans { .. } => "mini-batch k means",
                     PosterizeMethod::GaussianMixture { .. } => "gaussian mixture",
+                    PosterizeMethod::Ward { .. } => "ward",
                 })
                 .show_ui(ui, |ui| {
                     let selected = matches!(self.method, PosterizeMethod::KMeans { .. });
@@ -163,6 +186,18 @@ impl MyEguiApp {
                         self.method = PosterizeMethod::KMeans {
                             cluster_init : ClusterInit::Llyod,
                             cluster_count : NonZero::new(3).unwrap(),
+                            empty_cluster_strategy : EmptyClusterStrategy::ReseedFarthest,
+                            restarts : NonZero::new(1).unwrap(),
+                        };
+                    }
+
+                    let selected = matches!(self.method, PosterizeMethod::MiniBatchKMeans { .. });
+                    if ui.selectable_label(selected, "mini-batch k means").clicked() && !selected {
+                        self.method = PosterizeMethod::MiniBatchKMeans {
+                            cluster_init : ClusterInit::Llyod,
+                            cluster_count : NonZero::new(3).unwrap(),
+                            batch_size : NonZero::new(1024).unwrap(),
+                            min_learning_rate : 0.0,
                         };
                     }
 
@@ -171,13 +206,58 @@ impl MyEguiApp {
                         self.method = PosterizeMethod::GaussianMixture {
                             cluster_init : ClusterInit::Llyod,
                             cluster_count : NonZero::new(3).unwrap(),
+                            covariance_type : CovarianceType::Full,
+                            covariance_regularization : 1e-6,
+                            restarts : NonZero::new(1).unwrap(),
+                        };
+                    }
+
+                    let selected = matches!(self.method, PosterizeMethod::Ward { .. });
+                    if ui.selectable_label(selected, "ward").clicked() && !selected {
+                        self.method = PosterizeMethod::Ward {
+                            cluster_count : NonZero::new(3).unwrap(),
+                            bin_width : 8.0,
+                            max_bins : NonZero::new(4096).unwrap(),
                         };
                     }
                 });
 
             // Method specific options
             match &mut self.method {
-                PosterizeMethod::KMeans { cluster_init, cluster_count } => {
+                PosterizeMethod::KMeans { cluster_init, cluster_count, empty_cluster_strategy, restarts } => {
+                    egui::ComboBox::from_label("Cluster init")
+                        .selected_text(match cluster_init {
+                            ClusterInit::Llyod => "llyod",
+                            ClusterInit::KMeanPlusPlus => "k mean plus plus",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(cluster_init, ClusterInit::Llyod, "llyod");
+                            ui.selectable_value(cluster_init, ClusterInit::KMeanPlusPlus, "k mean plus plus");
+                        });
+
+                    let mut cluster_count_inner = cluster_count.get();
+                    ui.add(egui::Slider::new(&mut cluster_count_inner, 1..=128).text("Cluster count"));
+                    *cluster_count = NonZero::new(cluster_count_inner).unwrap();
+
+                    egui::ComboBox::from_label("Empty cluster strategy")
+                        .selected_text(match empty_cluster_strategy {
+                            EmptyClusterStrategy::KeepPrevious => "keep previous",
+                            EmptyClusterStrategy::ReseedFarthest => "reseed farthest",
+                            EmptyClusterStrategy::SplitLargest => "split largest",
+                            EmptyClusterStrategy::Drop => "drop",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(empty_cluster_strategy, EmptyClusterStrategy::KeepPrevious, "keep previous");
+                            ui.selectable_value(empty_cluster_strategy, EmptyClusterStrategy::ReseedFarthest, "reseed farthest");
+                            ui.selectable_value(empty_cluster_strategy, EmptyClusterStrategy::SplitLargest, "split largest");
+                            ui.selectable_value(empty_cluster_strategy, EmptyClusterStrategy::Drop, "drop");
+                        });
+
+                    let mut restarts_inner = restarts.get();
+                    ui.add(egui::Slider::new(&mut restarts_inner, 1..=16).text("Restarts"));
+                    *restarts = NonZero::new(restarts_inner).unwrap();
+                },
+                PosterizeMethod::MiniBatchKMeans { cluster_init, cluster_count, batch_size, min_learning_rate } => {
                     egui::ComboBox::from_label("Cluster init")
                         .selected_text(match cluster_init {
                             ClusterInit::Llyod => "llyod",
@@ -191,8 +271,14 @@ impl MyEguiApp {
                     let mut cluster_count_inner = cluster_count.get();
                     ui.add(egui::Slider::new(&mut cluster_count_inner, 1..=128).text("Cluster count"));
                     *cluster_count = NonZero::new(cluster_count_inner).unwrap();
+
+                    let mut batch_size_inner = batch_size.get();
+                    ui.add(egui::Slider::new(&mut batch_size_inner, 16..=65536).logarithmic(true).text("Batch size"));
+                    *batch_size = NonZero::new(batch_size_inner).unwrap();
+
+                    ui.add(egui::Slider::new(min_learning_rate, 0.0..=0.1).text("Min learning rate"));
                 },
-                PosterizeMethod::GaussianMixture { cluster_init, cluster_count } => {
+                PosterizeMethod::GaussianMixture { cluster_init, cluster_count, covariance_type, covariance_regularization, restarts } => {
                     egui::ComboBox::from_label("Cluster init")
                         .selected_text(match cluster_init {
                             ClusterInit::Llyod => "llyod",
@@ -206,6 +292,37 @@ impl MyEguiApp {
                     let mut cluster_count_inner = cluster_count.get();
                     ui.add(egui::Slider::new(&mut cluster_count_inner, 1..=128).text("Cluster count"));
                     *cluster_count = NonZero::new(cluster_count_inner).unwrap();
+
+                    egui::ComboBox::from_label("Covariance type")
+                        .selected_text(match covariance_type {
+                            CovarianceType::Full => "full",
+                            CovarianceType::Diagonal => "diagonal",
+                            CovarianceType::Spherical => "spherical",
+                            CovarianceType::Tied => "tied",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(covariance_type, CovarianceType::Full, "full");
+                            ui.selectable_value(covariance_type, CovarianceType::Diagonal, "diagonal");
+                            ui.selectable_value(covariance_type, CovarianceType::Spherical, "spherical");
+                            ui.selectable_value(covariance_type, CovarianceType::Tied, "tied");
+                        });
+
+                    ui.add(egui::Slider::new(covariance_regularization, 1e-9..=1e2).logarithmic(true).text("Covariance regularization"));
+
+                    let mut restarts_inner = restarts.get();
+                    ui.add(egui::Slider::new(&mut restarts_inner, 1..=16).text("Restarts"));
+                    *restarts = NonZero::new(restarts_inner).unwrap();
+                },
+                PosterizeMethod::Ward { cluster_count, bin_width, max_bins } => {
+                    let mut cluster_count_inner = cluster_count.get();
+                    ui.add(egui::Slider::new(&mut cluster_count_inner, 1..=128).text("Cluster count"));
+                    *cluster_count = NonZero::new(cluster_count_inner).unwrap();
+
+                    ui.add(egui::Slider::new(bin_width, 1.0..=64.0).logarithmic(true).text("Bin width"));
+
+                    let mut max_bins_inner = max_bins.get();
+                    ui.add(egui::Slider::new(&mut max_bins_inner, 16..=16384).logarithmic(true).text("Max bins"));
+                    *max_bins = NonZero::new(max_bins_inner).unwrap();
                 },
             };
         });
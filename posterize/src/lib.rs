@@ -1,48 +1,265 @@
-//! This crate implement image posterization using various(i.e. 2) clustering algorithms.
+//! This crate implement image posterization using various(i.e. 4) clustering algorithms.
 
 pub use cluster::expectation_maximization::init::ClusterInit;
+pub use cluster::expectation_maximization::gaussian_mixture::CovarianceType;
+pub use cluster::expectation_maximization::k_means::EmptyClusterStrategy;
 
 use cluster::expectation_maximization::k_means::*;
+use cluster::expectation_maximization::mini_batch_k_means::*;
 use cluster::expectation_maximization::gaussian_mixture::*;
+use cluster::expectation_maximization::restarts::Restarts;
+use cluster::hierarchical::agglomerative::ward::ward_weighted;
 
 use math::prelude::*;
 use rand::prelude::*;
 
 use clap::Subcommand;
+use std::collections::BTreeMap;
 use std::num::NonZero;
 
 /// Enum containing different clustering algorithms that can be used for posterization.
 ///
-/// While there are many more clustering algorithm implemented in the cluster crate, only kmeans
-/// and gaussian mixture are supported because other algorithm are computational infeasible (At
-/// least in my implementation).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Subcommand)]
+/// While there are many more clustering algorithm implemented in the cluster crate, only kmeans,
+/// mini-batch kmeans, gaussian mixture and ward are supported because other algorithm are computational infeasible (At
+/// least in my implementation). Even ward is only feasible on a reduced set of colors.
+#[derive(Debug, Clone, Copy, PartialEq, Subcommand)]
 pub enum PosterizeMethod {
-    KMeans { cluster_init : ClusterInit, cluster_count : NonZero<usize>, },
-    GaussianMixture { cluster_init : ClusterInit, cluster_count : NonZero<usize>, },
+    KMeans {
+        cluster_init : ClusterInit,
+        cluster_count : NonZero<usize>,
+        #[arg(long, value_enum, default_value_t = EmptyClusterStrategy::ReseedFarthest)]
+        empty_cluster_strategy : EmptyClusterStrategy,
+        /// Number of random initializations, keeping the one with lowest inertia
+        #[arg(long, default_value = "1")]
+        restarts : NonZero<usize>,
+    },
+    /// Much faster than k-means for large images at the cost of slightly worse quality
+    MiniBatchKMeans {
+        cluster_init : ClusterInit,
+        cluster_count : NonZero<usize>,
+        /// Number of pixels drawn in each iteration
+        #[arg(long, default_value = "1024")]
+        batch_size : NonZero<usize>,
+        /// Lower bound on per-cluster learning rate, in [0, 1]
+        #[arg(long, default_value = "0", value_parser = parse_unit_interval)]
+        min_learning_rate : f64,
+    },
+    GaussianMixture {
+        cluster_init : ClusterInit,
+        cluster_count : NonZero<usize>,
+        #[arg(long, value_enum, default_value_t = CovarianceType::Full)]
+        covariance_type : CovarianceType,
+        #[arg(long, default_value_t = 1e-6)]
+        covariance_regularization : f64,
+        /// Number of random initializations, keeping the one with highest log likelihood
+        #[arg(long, default_value = "1")]
+        restarts : NonZero<usize>,
+    },
+    /// Ward's minimum variance hierarchical clustering on a color histogram
+    Ward {
+        cluster_count : NonZero<usize>,
+        /// Width of histogram bins along each channel, in units of pixel values
+        #[arg(long, default_value = "8", value_parser = parse_positive)]
+        bin_width : f64,
+        /// Maximum number of histogram bins to cluster, drawn at random if there are more
+        #[arg(long, default_value = "4096")]
+        max_bins : NonZero<usize>,
+    },
 }
 
 impl PosterizeMethod {
     /// Posterize using the specified method.
     ///
     /// Apply the specified clustering algorithm to provided samples and replace each sample with
-    /// the center of the cluster it belongs to.
-    pub fn posterize<const N: usize>(self, samples : &mut [Vector<N>]) {
+    /// the center of the cluster it belongs to. All randomness is drawn from rng, so the output is
+    /// reproducible given a seeded rng.
+    pub fn posterize<const N: usize, R: Rng>(self, samples : &mut [Vector<N>], rng : &mut R) {
         let sample_count = samples.len();
         match self {
-            PosterizeMethod::KMeans { cluster_init, cluster_count } => {
-                let (means, labels, _) = KMeans::new(samples.len(), cluster_count.into()).run(samples, cluster_init, &mut thread_rng());
+            PosterizeMethod::KMeans { cluster_init, cluster_count, empty_cluster_strategy, restarts } => {
+                let k_means = KMeans { empty_cluster_strategy, ..KMeans::new(samples.len(), cluster_count.into()) };
+                let restarts = Restarts { count : restarts.into(), parallel : true };
+                let (means, labels, _, _, _) = k_means.run_restarts(samples, cluster_init, restarts, rng);
                 for (index, pixel) in samples.iter_mut().enumerate() {
                     *pixel = means[labels[index]];
                 }
             }
-            PosterizeMethod::GaussianMixture { cluster_init, cluster_count } => {
-                let (_, means, _, _, _, _, posteriors) = GaussianMixture::new(samples.len(), cluster_count.into()).run(samples, cluster_init, &mut thread_rng());
+            PosterizeMethod::MiniBatchKMeans { cluster_init, cluster_count, batch_size, min_learning_rate } => {
+                let mini_batch_k_means = MiniBatchKMeans { batch_size : batch_size.into(), min_learning_rate, ..MiniBatchKMeans::new(samples.len(), cluster_count.into()) };
+                let (means, labels, _, _) = mini_batch_k_means.run(samples, cluster_init, rng);
+                for (index, pixel) in samples.iter_mut().enumerate() {
+                    *pixel = means[labels[index]];
+                }
+            }
+            PosterizeMethod::GaussianMixture { cluster_init, cluster_count, covariance_type, covariance_regularization, restarts } => {
+                let gaussian_mixture = GaussianMixture { covariance_type, covariance_regularization, ..GaussianMixture::new(samples.len(), cluster_count.into()) };
+                let restarts = Restarts { count : restarts.into(), parallel : true };
+                let (_, means, _, _, _, _, posteriors, _, _, _) = gaussian_mixture.run_restarts(samples, cluster_init, restarts, rng);
                 for (index, pixel) in samples.iter_mut().enumerate() {
                     *pixel = (0..cluster_count.into()).map(|cluster_index| means[cluster_index] * posteriors[cluster_index * sample_count + index]).sum();
                 }
             }
+            PosterizeMethod::Ward { cluster_count, bin_width, max_bins } => {
+                let means = ward_means(samples, cluster_count.into(), bin_width, max_bins.into(), rng);
+                for pixel in samples.iter_mut() {
+                    *pixel = *means.iter().min_by(|a, b| f64::total_cmp(&pixel.squared_distance(a), &pixel.squared_distance(b))).unwrap();
+                }
+            }
         }
     }
+
+    /// Posterize using the specified method with a rng seeded from seed.
+    ///
+    /// Identical seeds give identical output for identical samples.
+    pub fn posterize_with_seed<const N: usize>(self, samples : &mut [Vector<N>], seed : u64) {
+        self.posterize(samples, &mut StdRng::seed_from_u64(seed))
+    }
+}
+
+/// Parse a positive finite number from command line.
+fn parse_positive(value : &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        Ok(_) => Err("must be a positive number".to_string()),
+        Err(error) => Err(error.to_string()),
+    }
 }
 
+/// Parse a number in [0, 1] from command line.
+fn parse_unit_interval(value : &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        Ok(_) => Err("must be a number in [0, 1]".to_string()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Cluster means of Ward's clustering on a histogram of samples.
+///
+/// Each bin is represented by the mean of its samples, weighted by their number. If there are more
+/// than max_bins bins, max_bins of them are drawn at random. Return at most cluster_count means.
+fn ward_means<const N: usize, R: Rng>(samples : &[Vector<N>], cluster_count : usize, bin_width : f64, max_bins : usize, rng : &mut R) -> Vec<Vector<N>> {
+    assert!(bin_width > 0.0);
+
+    // Ordered map, so that the result only depends on rng.
+    let mut bins = BTreeMap::<[i64; N], (Vector<N>, usize)>::new();
+    for sample in samples {
+        let key = sample.into_array().map(|value| (value / bin_width).floor() as i64);
+        let (sum, count) = bins.entry(key).or_insert((Vector::zero(), 0));
+        *sum += *sample;
+        *count += 1;
+    }
+
+    let mut bins = bins.into_values().collect::<Vec<_>>();
+    if bins.len() > max_bins {
+        bins = bins.choose_multiple(rng, max_bins).copied().collect();
+    }
+
+    let bin_means = bins.iter().map(|&(sum, count)| sum / count as f64).collect::<Vec<_>>();
+    let bin_weights = bins.iter().map(|&(_, count)| count as f64).collect::<Vec<_>>();
+    let labels = ward_weighted(&bin_means, &bin_weights).section_with_cluster_count(cluster_count).labels;
+
+    let mut clusters = vec![(Vector::zero(), 0); labels.iter().max().map_or(0, |&label| label + 1)];
+    for (&label, &(sum, count)) in std::iter::zip(&labels, &bins) {
+        clusters[label].0 += sum;
+        clusters[label].1 += count;
+    }
+    clusters.into_iter().map(|(sum, count)| sum / count as f64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(subcommand)]
+        method : PosterizeMethod,
+    }
+
+    #[test]
+    fn test_seed() {
+        let samples = (0..128)
+            .map(|_| Vector::from_array([thread_rng().gen_range(0.0..256.0), thread_rng().gen_range(0.0..256.0), thread_rng().gen_range(0.0..256.0)]))
+            .collect::<Vec<_>>();
+
+        let methods = [
+            PosterizeMethod::KMeans {
+                cluster_init : ClusterInit::Llyod,
+                cluster_count : NonZero::new(4).unwrap(),
+                empty_cluster_strategy : EmptyClusterStrategy::ReseedFarthest,
+                restarts : NonZero::new(2).unwrap(),
+            },
+            PosterizeMethod::MiniBatchKMeans {
+                cluster_init : ClusterInit::KMeanPlusPlus,
+                cluster_count : NonZero::new(4).unwrap(),
+                batch_size : NonZero::new(32).unwrap(),
+                min_learning_rate : 0.0,
+            },
+            PosterizeMethod::GaussianMixture {
+                cluster_init : ClusterInit::KMeanPlusPlus,
+                cluster_count : NonZero::new(4).unwrap(),
+                covariance_type : CovarianceType::Full,
+                covariance_regularization : 1e-6,
+                restarts : NonZero::new(2).unwrap(),
+            },
+            PosterizeMethod::Ward {
+                cluster_count : NonZero::new(4).unwrap(),
+                bin_width : 16.0,
+                max_bins : NonZero::new(64).unwrap(),
+            },
+        ];
+
+        for method in methods {
+            let mut output1 = samples.clone();
+            let mut output2 = samples.clone();
+            method.posterize_with_seed(&mut output1, 42);
+            method.posterize_with_seed(&mut output2, 42);
+            assert!(std::iter::zip(&output1, &output2).all(|(a, b)| a.as_slice() == b.as_slice()), "method = {method:?}");
+        }
+    }
+
+    #[test]
+    fn test_mini_batch_k_means_arguments() {
+        let method = Cli::try_parse_from(["posterize", "mini-batch-k-means", "llyod", "4"]).unwrap().method;
+        assert_eq!(method, PosterizeMethod::MiniBatchKMeans { cluster_init : ClusterInit::Llyod, cluster_count : NonZero::new(4).unwrap(), batch_size : NonZero::new(1024).unwrap(), min_learning_rate : 0.0 });
+        for min_learning_rate in ["0", "0.5", "1"] {
+            assert!(Cli::try_parse_from(["posterize", "mini-batch-k-means", "llyod", "4", "--min-learning-rate", min_learning_rate]).is_ok());
+        }
+        for min_learning_rate in ["-0.1", "1.5", "NaN", "abc"] {
+            assert!(Cli::try_parse_from(["posterize", "mini-batch-k-means", "llyod", "4", "--min-learning-rate", min_learning_rate]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_ward() {
+        // Two groups of colors spread over many bins, more than are clustered.
+        let mut rng = thread_rng();
+        let mut samples = (0..1000)
+            .map(|index| if index % 2 == 0 { 40.0 } else { 200.0 })
+            .map(|center| Vector::from_array([0, 1, 2].map(|_| center + rng.gen_range(-20.0..20.0))))
+            .collect::<Vec<_>>();
+
+        let means = ward_means(&samples, 2, 4.0, 256, &mut rng);
+        assert_eq!(means.len(), 2);
+        assert!(means.iter().any(|mean| mean.as_slice().iter().all(|value| (value - 40.0).abs() < 10.0)));
+        assert!(means.iter().any(|mean| mean.as_slice().iter().all(|value| (value - 200.0).abs() < 10.0)));
+
+        let method = PosterizeMethod::Ward { cluster_count : NonZero::new(2).unwrap(), bin_width : 4.0, max_bins : NonZero::new(256).unwrap() };
+        method.posterize(&mut samples, &mut rng);
+        assert_eq!(samples[0].as_slice(), samples[2].as_slice());
+        assert_eq!(samples[1].as_slice(), samples[3].as_slice());
+        assert_ne!(samples[0].as_slice(), samples[1].as_slice());
+    }
+
+    #[test]
+    fn test_ward_arguments() {
+        let method = Cli::try_parse_from(["posterize", "ward", "4"]).unwrap().method;
+        assert_eq!(method, PosterizeMethod::Ward { cluster_count : NonZero::new(4).unwrap(), bin_width : 8.0, max_bins : NonZero::new(4096).unwrap() });
+        for bin_width in ["0", "-1", "inf", "NaN", "abc"] {
+            assert!(Cli::try_parse_from(["posterize", "ward", "4", "--bin-width", bin_width]).is_err());
+        }
+    }
+}